@@ -0,0 +1,268 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Expr, Fields, Ident, LitStr, Token, Type};
+
+/// A single `key` or `key = value` inside `#[stat(..)]`.
+struct StatArg {
+    key: Ident,
+    value: Option<Expr>,
+}
+
+impl Parse for StatArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key = input.parse()?;
+        let value = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            key: key,
+            value: value,
+        })
+    }
+}
+
+#[derive(Default)]
+struct Stat {
+    marker: bool,
+    name: Option<Expr>,
+    value: Option<Expr>,
+    base: Option<Expr>,
+    min: Option<Expr>,
+    max: Option<Expr>,
+    regen: Option<Expr>,
+}
+
+impl Stat {
+    fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut stat = Stat::default();
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("stat")) {
+            let args = attr.parse_args_with(Punctuated::<StatArg, Token![,]>::parse_terminated)?;
+            for arg in args {
+                let key = arg.key.to_string();
+                let (slot, value) = match (key.as_str(), arg.value) {
+                    ("marker", None) => {
+                        stat.marker = true;
+                        continue;
+                    }
+                    ("name", Some(value)) => (&mut stat.name, value),
+                    ("value", Some(value)) => (&mut stat.value, value),
+                    ("base", Some(value)) => (&mut stat.base, value),
+                    ("min", Some(value)) => (&mut stat.min, value),
+                    ("max", Some(value)) => (&mut stat.max, value),
+                    ("regen", Some(value)) => (&mut stat.regen, value),
+                    _ => {
+                        return Err(Error::new(
+                            arg.key.span(),
+                            format!(
+                                "unknown stat argument `{}`, expected `marker` or one of \
+                                 `name`, `value`, `base`, `min`, `max`, `regen` with a value",
+                                key
+                            ),
+                        ))
+                    }
+                };
+
+                if slot.is_some() {
+                    return Err(Error::new(
+                        arg.key.span(),
+                        format!("duplicate stat argument `{}`", key),
+                    ));
+                }
+                *slot = Some(value);
+            }
+        }
+
+        Ok(stat)
+    }
+}
+
+/// Generates a bundle and a plugin from a struct listing attributes.
+///
+/// Every field is an attribute, its type is the attribute's marker type and `#[stat(..)]`
+/// declares its starting values:
+///
+/// - `marker`: also generate the marker type as a unit struct.
+/// - `name = "Health"`: name in the `AttributeRegistry`.
+/// - `base = ..`: `Attribute<Base<A>>`.
+/// - `min = ..`: `Attribute<Min<A>>`.
+/// - `max = ..`: `Attribute<Max<A>>` and `Attribute<Base<Max<A>>>`.
+/// - `regen = ..`: `Attribute<Regen<A>>`.
+/// - `value = ..`: starting value of `Attribute<A>`, defaults to the base, then the max, then 0.
+///
+/// Values are any expression accepted by `Amount::from_num`.
+///
+/// ```ignore
+/// #[derive(StatSheet)]
+/// pub struct Champion {
+///     #[stat(max = 580, min = 0, regen = 2.5)]
+///     health: Health,
+///     #[stat(marker, base = 30)]
+///     armor: Armor,
+/// }
+/// ```
+///
+/// generates `Armor`, a `ChampionBundle` whose `Default` and `From<Champion>` hold those values
+/// and a `ChampionPlugin` adding an `AttributePlugin` for every field.
+///
+/// Every attribute can only be listed once, two fields of the same type would put the same
+/// components into the bundle twice.
+#[proc_macro_derive(StatSheet, attributes(stat))]
+pub fn derive_stat_sheet(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match stat_sheet(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn stat_sheet(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    input.ident.span(),
+                    "StatSheet can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.ident.span(),
+                "StatSheet can only be derived for structs",
+            ))
+        }
+    };
+
+    let forte = quote!(::forte::ability::attribute);
+    let amount = quote!(#forte::amount::Amount);
+    let vis = &input.vis;
+    let sheet = &input.ident;
+    let bundle = format_ident!("{}Bundle", input.ident);
+    let plugin = format_ident!("{}Plugin", input.ident);
+
+    let mut markers = Vec::new();
+    let mut bundle_fields = Vec::new();
+    let mut bundle_values = Vec::new();
+    let mut plugins = Vec::new();
+    let mut idents = Vec::new();
+    let mut types = Vec::new();
+
+    for field in fields {
+        let ident = field.ident.as_ref().expect("fields are named");
+        let ty = &field.ty;
+        let stat = Stat::parse(&field.attrs)?;
+
+        let type_key = quote!(#ty).to_string();
+        if types.contains(&type_key) {
+            return Err(Error::new(
+                ty.span(),
+                format!("`{}` is listed more than once in this StatSheet", type_key),
+            ));
+        }
+        types.push(type_key);
+        idents.push(ident);
+
+        if stat.marker {
+            let marker = match ty {
+                Type::Path(path) if path.qself.is_none() => path.path.get_ident(),
+                _ => None,
+            }
+            .ok_or_else(|| {
+                Error::new(
+                    ty.span(),
+                    "`marker` needs the field type to be a plain identifier",
+                )
+            })?;
+
+            let serde = LitStr::new("::forte::serde", Span::call_site());
+            markers.push(quote! {
+                #[derive(Debug, Default, Clone, Copy, ::forte::serde::Serialize, ::forte::serde::Deserialize)]
+                #[serde(crate = #serde)]
+                #vis struct #marker;
+            });
+        }
+
+        let value = stat
+            .value
+            .as_ref()
+            .or(stat.base.as_ref())
+            .or(stat.max.as_ref())
+            .map(|value| quote!(#amount::from_num(#value)))
+            .unwrap_or_else(|| quote!(#amount::ZERO));
+        bundle_fields.push(quote!(pub #ident: #forte::attribute::Attribute<#ty>));
+        bundle_values.push(quote!(#ident: #forte::attribute::Attribute::new(#value)));
+
+        let mut component = |suffix: &str, wrapped: proc_macro2::TokenStream, value: &Expr| {
+            let name = format_ident!("{}_{}", ident, suffix);
+            bundle_fields.push(quote!(pub #name: #forte::attribute::Attribute<#wrapped>));
+            bundle_values
+                .push(quote!(#name: #forte::attribute::Attribute::new(#amount::from_num(#value))));
+        };
+
+        if let Some(base) = &stat.base {
+            component("base", quote!(#forte::attribute::Base<#ty>), base);
+        }
+        if let Some(min) = &stat.min {
+            component("min", quote!(#forte::attribute::Min<#ty>), min);
+        }
+        if let Some(max) = &stat.max {
+            component("max", quote!(#forte::attribute::Max<#ty>), max);
+            component(
+                "base_max",
+                quote!(#forte::attribute::Base<#forte::attribute::Max<#ty>>),
+                max,
+            );
+        }
+        if let Some(regen) = &stat.regen {
+            component("regen", quote!(#forte::regen::Regen<#ty>), regen);
+        }
+
+        let with_name = stat.name.as_ref().map(|name| quote!(.with_name(#name)));
+        plugins.push(quote! {
+            app.add_plugin(#forte::plugin::AttributePlugin::<#ty>::new()#with_name);
+        });
+    }
+
+    Ok(quote! {
+        #(#markers)*
+
+        #[derive(::forte::bevy::prelude::Bundle)]
+        #vis struct #bundle {
+            #(#bundle_fields,)*
+        }
+
+        impl ::std::default::Default for #bundle {
+            fn default() -> Self {
+                Self {
+                    #(#bundle_values,)*
+                }
+            }
+        }
+
+        impl ::std::convert::From<#sheet> for #bundle {
+            fn from(sheet: #sheet) -> Self {
+                let #sheet { #(#idents,)* } = sheet;
+                #(let _ = #idents;)*
+                Self::default()
+            }
+        }
+
+        #[derive(Default)]
+        #vis struct #plugin;
+
+        impl ::forte::bevy::prelude::Plugin for #plugin {
+            fn build(&self, app: &mut ::forte::bevy::prelude::App) {
+                #(#plugins)*
+            }
+        }
+    })
+}
@@ -0,0 +1,453 @@
+use bevy::reflect::impl_reflect_value;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use std::fmt;
+use std::iter::Sum;
+use std::ops;
+use std::str::FromStr;
+
+/// Decimal fixed point number with exactly 4 decimal places.
+///
+/// Stored as a count of ten-thousandths in an `i64`, so `0.1`, `0.05` and `0.0001` are all
+/// represented exactly and sums of them never drift.
+///
+/// Rounding: results with more than 4 decimal places (multiplication, division and
+/// conversion from floats or strings) are rounded to the nearest ten-thousandth, with ties
+/// rounded away from zero.
+///
+/// Overflow: the arithmetic operators saturate at `Amount::MIN`/`Amount::MAX` and never
+/// panic or wrap, dividing by zero saturates in the direction of the dividend (`0 / 0` is
+/// `0`). The `checked_*` methods return `None` instead.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(i64);
+
+impl_reflect_value!(Amount(PartialEq, Hash, Serialize, Deserialize));
+
+const SCALE: i64 = 10_000;
+
+// `numerator / denominator`, rounded to nearest with ties away from zero.
+fn div_round(numerator: i128, denominator: i128) -> i128 {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+    if 2 * remainder.abs() >= denominator.abs() {
+        quotient + numerator.signum() * denominator.signum()
+    } else {
+        quotient
+    }
+}
+
+fn narrow(raw: i128) -> Option<i64> {
+    if raw > i64::MAX as i128 || raw < i64::MIN as i128 {
+        None
+    } else {
+        Some(raw as i64)
+    }
+}
+
+fn saturate(raw: i128) -> i64 {
+    raw.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+impl Amount {
+    /// Number of decimal places.
+    pub const DECIMALS: u32 = 4;
+    pub const ZERO: Amount = Amount(0);
+    pub const ONE: Amount = Amount(SCALE);
+    /// Smallest positive amount, `0.0001`.
+    pub const EPSILON: Amount = Amount(1);
+    pub const MIN: Amount = Amount(i64::MIN);
+    pub const MAX: Amount = Amount(i64::MAX);
+
+    /// Amount from a count of ten-thousandths.
+    pub const fn from_raw(raw: i64) -> Self {
+        Self(raw)
+    }
+
+    /// Count of ten-thousandths.
+    pub const fn raw(self) -> i64 {
+        self.0
+    }
+
+    pub fn from_num<N: IntoAmount>(num: N) -> Self {
+        num.into_amount()
+    }
+
+    pub fn to_f32(self) -> f32 {
+        self.0 as f32 / SCALE as f32
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / SCALE as f64
+    }
+
+    /// Integer part, rounded towards zero.
+    pub fn to_i64(self) -> i64 {
+        self.0 / SCALE
+    }
+
+    pub fn floor(self) -> Self {
+        Self(self.0.div_euclid(SCALE).saturating_mul(SCALE))
+    }
+
+    pub fn ceil(self) -> Self {
+        let floor = self.floor();
+        if floor == self {
+            floor
+        } else {
+            floor.saturating_add(Amount::ONE)
+        }
+    }
+
+    /// Rounded to the nearest integer, ties away from zero.
+    pub fn round(self) -> Self {
+        Self(saturate(
+            div_round(self.0 as i128, SCALE as i128) * SCALE as i128,
+        ))
+    }
+
+    pub fn abs(self) -> Self {
+        Self(self.0.saturating_abs())
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Self)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).map(Self)
+    }
+
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        narrow(div_round(self.0 as i128 * rhs.0 as i128, SCALE as i128)).map(Self)
+    }
+
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        if rhs.0 == 0 {
+            return None;
+        }
+
+        narrow(div_round(self.0 as i128 * SCALE as i128, rhs.0 as i128)).map(Self)
+    }
+
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+
+    pub fn saturating_mul(self, rhs: Self) -> Self {
+        Self(saturate(div_round(
+            self.0 as i128 * rhs.0 as i128,
+            SCALE as i128,
+        )))
+    }
+
+    pub fn saturating_div(self, rhs: Self) -> Self {
+        if rhs.0 == 0 {
+            return match self.0.signum() {
+                1 => Amount::MAX,
+                -1 => Amount::MIN,
+                _ => Amount::ZERO,
+            };
+        }
+
+        Self(saturate(div_round(
+            self.0 as i128 * SCALE as i128,
+            rhs.0 as i128,
+        )))
+    }
+}
+
+/// Numbers that can be converted into an `Amount`, saturating when out of range.
+pub trait IntoAmount {
+    fn into_amount(self) -> Amount;
+}
+
+macro_rules! impl_into_amount_int {
+    ($($int:ty),*) => {
+        $(
+            impl IntoAmount for $int {
+                fn into_amount(self) -> Amount {
+                    Amount(saturate(self as i128 * SCALE as i128))
+                }
+            }
+
+            impl From<$int> for Amount {
+                fn from(num: $int) -> Self {
+                    num.into_amount()
+                }
+            }
+        )*
+    };
+}
+
+impl_into_amount_int!(i8, i16, i32, i64, u8, u16, u32, u64, usize);
+
+impl IntoAmount for f64 {
+    fn into_amount(self) -> Amount {
+        // `as` saturates and maps NaN to zero.
+        Amount((self * SCALE as f64).round() as i64)
+    }
+}
+
+impl IntoAmount for f32 {
+    fn into_amount(self) -> Amount {
+        (self as f64).into_amount()
+    }
+}
+
+impl IntoAmount for Amount {
+    fn into_amount(self) -> Amount {
+        self
+    }
+}
+
+macro_rules! impl_op {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $saturating:ident) => {
+        impl ops::$trait for Amount {
+            type Output = Amount;
+            fn $method(self, rhs: Amount) -> Amount {
+                self.$saturating(rhs)
+            }
+        }
+
+        impl<'a> ops::$trait<&'a Amount> for Amount {
+            type Output = Amount;
+            fn $method(self, rhs: &'a Amount) -> Amount {
+                self.$saturating(*rhs)
+            }
+        }
+
+        impl<'a> ops::$trait<Amount> for &'a Amount {
+            type Output = Amount;
+            fn $method(self, rhs: Amount) -> Amount {
+                self.$saturating(rhs)
+            }
+        }
+
+        impl<'a, 'b> ops::$trait<&'b Amount> for &'a Amount {
+            type Output = Amount;
+            fn $method(self, rhs: &'b Amount) -> Amount {
+                self.$saturating(*rhs)
+            }
+        }
+
+        impl ops::$assign_trait for Amount {
+            fn $assign_method(&mut self, rhs: Amount) {
+                *self = self.$saturating(rhs);
+            }
+        }
+    };
+}
+
+impl_op!(Add, add, AddAssign, add_assign, saturating_add);
+impl_op!(Sub, sub, SubAssign, sub_assign, saturating_sub);
+impl_op!(Mul, mul, MulAssign, mul_assign, saturating_mul);
+impl_op!(Div, div, DivAssign, div_assign, saturating_div);
+
+impl ops::Neg for Amount {
+    type Output = Amount;
+    fn neg(self) -> Amount {
+        Amount(self.0.saturating_neg())
+    }
+}
+
+impl Sum for Amount {
+    fn sum<I: Iterator<Item = Amount>>(iter: I) -> Self {
+        iter.fold(Amount::ZERO, |total, amount| total + amount)
+    }
+}
+
+impl<'a> Sum<&'a Amount> for Amount {
+    fn sum<I: Iterator<Item = &'a Amount>>(iter: I) -> Self {
+        iter.fold(Amount::ZERO, |total, amount| total + amount)
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let raw = self.0.unsigned_abs();
+        let integer = raw / SCALE as u64;
+        let fraction = raw % SCALE as u64;
+        if fraction == 0 {
+            write!(f, "{}{}", sign, integer)
+        } else {
+            let fraction = format!("{:04}", fraction);
+            write!(f, "{}{}.{}", sign, integer, fraction.trim_end_matches('0'))
+        }
+    }
+}
+
+impl fmt::Debug for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Amount({})", self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseAmountError(String);
+
+impl fmt::Display for ParseAmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid amount `{}`", self.0)
+    }
+}
+
+impl std::error::Error for ParseAmountError {}
+
+impl FromStr for Amount {
+    type Err = ParseAmountError;
+
+    /// Parses decimals like `-12.5`, rounding digits past the fourth decimal place.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseAmountError(s.to_string());
+        let (negative, digits) = match s.trim().strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.trim()),
+        };
+        let (integer, fraction) = match digits.split_once('.') {
+            Some((integer, fraction)) => (integer, fraction),
+            None => (digits, ""),
+        };
+
+        let all_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if (integer.is_empty() && fraction.is_empty())
+            || !all_digits(integer)
+            || !all_digits(fraction)
+        {
+            return Err(error());
+        }
+
+        let mut raw: i128 = 0;
+        for digit in integer
+            .chars()
+            .chain(fraction.chars().chain("0000".chars()).take(4))
+        {
+            raw = raw * 10 + digit.to_digit(10).unwrap() as i128;
+            if raw > i64::MAX as i128 + 1 {
+                return Err(error());
+            }
+        }
+
+        if let Some(next) = fraction.chars().nth(4) {
+            if next >= '5' {
+                raw += 1;
+            }
+        }
+
+        let raw = if negative { -raw } else { raw };
+        narrow(raw).map(Amount).ok_or_else(error)
+    }
+}
+
+impl Serialize for Amount {
+    /// Serialized as a decimal string so no precision is lost in any format.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    /// Accepts decimal strings as well as plain integers and floats.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AmountVisitor;
+
+        impl<'de> de::Visitor<'de> for AmountVisitor {
+            type Value = Amount;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "a number or a decimal string")
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Amount, E> {
+                Ok(Amount::from_num(value))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Amount, E> {
+                Ok(Amount::from_num(value))
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Amount, E> {
+                Ok(Amount::from_num(value))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Amount, E> {
+                value.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimal_precision() {
+        let tenth = Amount::from_num(0.1);
+        assert_eq!(tenth + Amount::from_num(0.2), Amount::from_num(0.3));
+        assert_eq!(
+            (0..20).map(|_| Amount::from_num(0.05)).sum::<Amount>(),
+            Amount::ONE
+        );
+        assert_eq!("0.0001".parse::<Amount>().unwrap(), Amount::EPSILON);
+        assert_eq!(Amount::from_num(2.5).to_string(), "2.5");
+        assert_eq!(Amount::from_num(-0.25).to_string(), "-0.25");
+    }
+
+    #[test]
+    fn rounding() {
+        // 0.0001 * 0.5 = 0.00005, ties round away from zero.
+        assert_eq!(Amount::EPSILON * Amount::from_num(0.5), Amount::EPSILON);
+        assert_eq!(-Amount::EPSILON * Amount::from_num(0.5), -Amount::EPSILON);
+        assert_eq!(Amount::ONE / Amount::from_num(3), Amount::from_raw(3333));
+        assert_eq!(
+            Amount::from_num(2) / Amount::from_num(3),
+            Amount::from_raw(6667)
+        );
+        assert_eq!(
+            "1.23455".parse::<Amount>().unwrap(),
+            Amount::from_raw(12346)
+        );
+        assert_eq!(Amount::from_num(2.5).round(), Amount::from_num(3));
+        assert_eq!(Amount::from_num(-2.5).floor(), Amount::from_num(-3));
+        assert_eq!(Amount::from_num(2.0001).ceil(), Amount::from_num(3));
+    }
+
+    #[test]
+    fn overflow() {
+        let huge = Amount::MAX / Amount::from_num(2);
+        assert_eq!(huge * Amount::from_num(10), Amount::MAX);
+        assert_eq!(-huge * Amount::from_num(10), Amount::MIN);
+        assert_eq!(Amount::MAX + Amount::ONE, Amount::MAX);
+        assert_eq!(huge.checked_mul(Amount::from_num(10)), None);
+        assert_eq!(Amount::ONE.checked_div(Amount::ZERO), None);
+        assert_eq!(Amount::ONE / Amount::ZERO, Amount::MAX);
+    }
+
+    #[test]
+    fn serde() {
+        let amount = Amount::from_num(-12.3456);
+        let serialized = ron::to_string(&amount).unwrap();
+        assert_eq!(serialized, "\"-12.3456\"");
+        assert_eq!(ron::from_str::<Amount>(&serialized).unwrap(), amount);
+        assert_eq!(ron::from_str::<Amount>("7").unwrap(), Amount::from_num(7));
+        assert_eq!(
+            ron::from_str::<Amount>("0.5").unwrap(),
+            Amount::from_num(0.5)
+        );
+    }
+}
@@ -1,16 +1,35 @@
 use bevy::prelude::*;
-use fixed::{types::extra::U4, FixedI64};
+use bevy::reflect::impl_reflect_value;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use std::marker::PhantomData;
+pub use super::amount::Amount;
+use super::event::{change_attribute, AttributeChanged, ChangeCause};
+use super::modifier::ModifierTotals;
+use super::pipeline::{AttributePipeline, ModifierValues};
 
-pub type Amount = FixedI64<U4>;
+/// Everything an attribute marker needs to be registered with an `AttributePlugin`.
+///
+/// Serialization is required so every `Attribute<A>` can be part of scenes and save files,
+/// deriving `Default, Clone, Serialize, Deserialize` on a unit struct is enough.
+pub trait AttributeType:
+    'static + Send + Sync + Default + Clone + Serialize + DeserializeOwned
+{
+}
+
+impl<A> AttributeType for A where
+    A: 'static + Send + Sync + Default + Clone + Serialize + DeserializeOwned
+{
+}
 
-#[derive(Component, Debug, Clone)]
+#[derive(Component, Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Attribute<A> {
     amount: Amount,
     inner: A,
 }
 
+// Reflected as a value, so the whole attribute goes through serde in scenes.
+impl_reflect_value!(Attribute<A: AttributeType>(Component, Serialize, Deserialize));
+
 impl<A> Attribute<A>
 where
     A: Default,
@@ -48,145 +67,220 @@ impl<A> Attribute<A> {
     }
 }
 
-pub struct Base<A>(PhantomData<A>);
+/// Type name of `A` with module paths stripped, `forte::..::Max<game::Health>` becomes
+/// `Max<Health>`.
+pub fn short_type_name<A>() -> String {
+    let full = std::any::type_name::<A>();
+    let mut short = String::with_capacity(full.len());
+    let mut segment = String::new();
+    for c in full.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            segment.push(c);
+        } else {
+            short.push_str(segment.rsplit("::").next().unwrap_or(""));
+            segment.clear();
+            short.push(c);
+        }
+    }
+    short.push_str(segment.rsplit("::").next().unwrap_or(""));
+    short
+}
+
+macro_rules! attribute_marker {
+    ($($(#[$meta:meta])* $name:ident),* $(,)?) => {
+        $(
+            $(#[$meta])*
+            #[derive(serde::Serialize, serde::Deserialize)]
+            #[serde(bound = "")]
+            pub struct $name<A>(std::marker::PhantomData<A>);
 
-pub struct Add<A>(PhantomData<A>);
+            bevy::reflect::impl_reflect_value!(
+                $name<A: 'static + Send + Sync>(Serialize, Deserialize)
+            );
+
+            impl<A> Default for $name<A> {
+                fn default() -> Self {
+                    Self(std::marker::PhantomData)
+                }
+            }
 
-pub struct Mult<A>(PhantomData<A>);
+            impl<A> Clone for $name<A> {
+                fn clone(&self) -> Self {
+                    Self(std::marker::PhantomData)
+                }
+            }
 
-pub struct Max<A>(PhantomData<A>);
+            impl<A> std::fmt::Debug for $name<A> {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    write!(f, "{}<{}>", stringify!($name), std::any::type_name::<A>())
+                }
+            }
+        )*
+    };
+}
 
-pub struct Min<A>(PhantomData<A>);
+pub(crate) use attribute_marker;
 
-pub struct Sub<A>(PhantomData<A>);
+attribute_marker!(
+    /// Base value of `A` before any modifiers are applied.
+    Base,
+    /// Flat amount added onto the base of `A`.
+    Add,
+    /// Additive percentage bonus to `A`, `0.25` is +25%.
+    Percent,
+    /// Multiplier applied to `A`.
+    Mult,
+    /// Flat amount subtracted from `A` after scaling.
+    Sub,
+    /// Final value of `A`, replaces the result of every other modifier.
+    Override,
+    /// Upper bound of `A`.
+    Max,
+    /// Lower bound of `A`.
+    Min,
+);
 
-fn basic_modifiers<A>(
+/// Recomputes `A` from `Base<A>` and its modifiers, in the order of `AttributePipeline<A>`.
+pub fn basic_modifiers<A>(
+    pipeline: Option<Res<AttributePipeline<A>>>,
+    mut events: EventWriter<AttributeChanged<A>>,
     mut attributes: Query<
         (
+            Entity,
             &mut Attribute<A>,
             Option<&Attribute<Base<A>>>,
             Option<&Attribute<Add<A>>>,
+            Option<&Attribute<Percent<A>>>,
             Option<&Attribute<Mult<A>>>,
+            Option<&Attribute<Sub<A>>>,
+            Option<&Attribute<Override<A>>>,
+            Option<&ModifierTotals<A>>,
         ),
         Or<(
             Changed<Attribute<Base<A>>>,
             Changed<Attribute<Add<A>>>,
+            Changed<Attribute<Percent<A>>>,
             Changed<Attribute<Mult<A>>>,
+            Changed<Attribute<Sub<A>>>,
+            Changed<Attribute<Override<A>>>,
+            Changed<ModifierTotals<A>>,
         )>,
     >,
 ) where
     A: 'static + Send + Sync,
 {
-    for (mut current, base, add, mult) in attributes.iter_mut() {
-        let base = base.map(|base| base.amount()).unwrap_or(&Amount::ZERO);
-        let mult = mult.map(|mult| mult.amount()).unwrap_or(&Amount::ONE);
-        let add = add.map(|add| add.amount()).unwrap_or(&Amount::ZERO);
-        let result = (base * mult) + add;
-
-        if *current.amount() != result {
-            current.set_amount(result);
+    let default_pipeline = AttributePipeline::<A>::default();
+    let pipeline = pipeline.as_deref().unwrap_or(&default_pipeline);
+
+    for (entity, mut current, base, add, percent, mult, sub, overridden, totals) in
+        attributes.iter_mut()
+    {
+        let base = base.map(|base| *base.amount()).unwrap_or(Amount::ZERO);
+        let mut values = ModifierValues::default();
+        if let Some(add) = add {
+            values.add = *add.amount();
+        }
+        if let Some(percent) = percent {
+            values.percent = *percent.amount();
         }
+        if let Some(mult) = mult {
+            values.mult = *mult.amount();
+        }
+        if let Some(sub) = sub {
+            values.sub = *sub.amount();
+        }
+        values.overridden = overridden.map(|overridden| *overridden.amount());
+        if let Some(totals) = totals {
+            values.add = values.add.saturating_add(totals.flat);
+            values.percent = values.percent.saturating_add(totals.percent);
+            values.mult = values.mult.saturating_mul(totals.mult);
+        }
+
+        let result = pipeline.evaluate(base, &values);
+        change_attribute(
+            &mut events,
+            entity,
+            &mut current,
+            result,
+            ChangeCause::Modifiers,
+        );
     }
 }
 
-fn clamp_max<A>(
+pub fn clamp_max<A>(
+    mut events: EventWriter<AttributeChanged<A>>,
     mut attributes: Query<
-        (&mut Attribute<A>, &Attribute<Max<A>>),
+        (Entity, &mut Attribute<A>, &Attribute<Max<A>>),
         Or<(Changed<Attribute<A>>, Changed<Attribute<Max<A>>>)>,
     >,
 ) where
     A: 'static + Send + Sync,
 {
-    for (mut current, max) in attributes.iter_mut() {
+    for (entity, mut current, max) in attributes.iter_mut() {
         if current.amount() > max.amount() {
-            current.set_amount(*max.amount());
+            change_attribute(
+                &mut events,
+                entity,
+                &mut current,
+                *max.amount(),
+                ChangeCause::Clamp,
+            );
         }
     }
 }
 
-fn clamp_min<A>(
+pub fn clamp_min<A>(
+    mut events: EventWriter<AttributeChanged<A>>,
     mut attributes: Query<
-        (&mut Attribute<A>, &Attribute<Min<A>>),
+        (Entity, &mut Attribute<A>, &Attribute<Min<A>>),
         Or<(Changed<Attribute<A>>, Changed<Attribute<Min<A>>>)>,
     >,
 ) where
     A: 'static + Send + Sync,
 {
-    for (mut current, min) in attributes.iter_mut() {
+    for (entity, mut current, min) in attributes.iter_mut() {
         if current.amount() < min.amount() {
-            current.set_amount(*min.amount());
+            change_attribute(
+                &mut events,
+                entity,
+                &mut current,
+                *min.amount(),
+                ChangeCause::Clamp,
+            );
         }
     }
 }
 
-pub struct SimpleAttributePlugin;
-
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
 pub struct Health;
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
 pub struct Energy;
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
 pub struct AttackSpeed;
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
 pub struct MovementSpeed;
 
-pub enum AttributeLabel {}
-
-impl Plugin for SimpleAttributePlugin {
-    fn build(&self, app: &mut App) {
-        app.add_system(
-            basic_modifiers::<Max<Health>>
-                .label("max_health_result")
-                .before("health_result"),
-        )
-        .add_system(
-            basic_modifiers::<Max<Health>>
-                .label("max_health_result")
-                .before("health_result"),
-        )
-        .add_system(basic_modifiers::<Health>.label("health_result"))
-        .add_system(
-            clamp_max::<Health>
-                .label("health_max")
-                .after("health_result"),
-        );
-    }
-}
-
+#[cfg(test)]
 mod test {
     use super::*;
-    use bevy::core::FixedTimestep;
-    use std::time::Duration;
+    use crate::ability::attribute::plugin::AttributePlugin;
+    use crate::ability::attribute::regen::Regen;
+    use bevy::ecs::entity::EntityMap;
+    use bevy::reflect::TypeRegistryArc;
+    use bevy::scene::{serde::SceneDeserializer, DynamicScene};
+    use serde::de::DeserializeSeed;
 
+    #[derive(Default, Clone, Serialize, Deserialize)]
     pub struct Health;
+    #[derive(Default, Clone, Serialize, Deserialize)]
     pub struct MovementSpeed;
 
     #[test]
     fn attribute_test() {
         let mut app = App::new();
         app.add_plugins(MinimalPlugins)
-            .add_system_to_stage(
-                CoreStage::Update,
-                basic_modifiers::<Max<Health>>
-                    .label("clamp_max_health")
-                    .before("health"),
-            )
-            .add_system_to_stage(
-                CoreStage::Update,
-                basic_modifiers::<Health>.label("attribute_health"),
-            )
-            .add_system_to_stage(
-                CoreStage::Update,
-                clamp_max::<Health>
-                    .label("health_max")
-                    .after("attribute_health"),
-            )
-            .add_system_to_stage(
-                CoreStage::Update,
-                clamp_max::<MovementSpeed>
-                    .label("movement_speed_max")
-                    .after("attribute_movement_speed"),
-            )
-            .add_system_to_stage(
-                CoreStage::Update,
-                basic_modifiers::<MovementSpeed>.label("attribute_movement_speed"),
-            );
+            .add_plugin(AttributePlugin::<Health>::default())
+            .add_plugin(AttributePlugin::<MovementSpeed>::default());
 
         let health = app
             .world
@@ -230,4 +324,56 @@ mod test {
             Amount::from_num(2)
         );
     }
+
+    #[test]
+    fn stat_sheet_scene_round_trip() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<Health>::default());
+
+        app.world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(42.5)))
+            .insert(Attribute::<Base<Health>>::new(Amount::from_num(50)))
+            .insert(Attribute::<Add<Health>>::new(Amount::from_num(5)))
+            .insert(Attribute::<Mult<Health>>::new(Amount::from_num(1.25)))
+            .insert(Attribute::<Regen<Health>>::new(Amount::from_num(2)))
+            .insert(Attribute::<Max<Health>>::new(Amount::from_num(100)))
+            .insert(Attribute::<Min<Health>>::new(Amount::ZERO));
+
+        let registry = app.world.get_resource::<TypeRegistryArc>().unwrap().clone();
+        let scene = DynamicScene::from_world(&app.world, &registry);
+        let serialized = scene.serialize_ron(&registry).unwrap();
+
+        let mut deserializer = ron::de::Deserializer::from_str(&serialized).unwrap();
+        let scene = SceneDeserializer {
+            type_registry: &registry.read(),
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+
+        let mut world = World::new();
+        world.insert_resource(registry);
+        scene
+            .write_to_world(&mut world, &mut EntityMap::default())
+            .unwrap();
+
+        let mut sheet = world.query::<(
+            &Attribute<Health>,
+            &Attribute<Base<Health>>,
+            &Attribute<Add<Health>>,
+            &Attribute<Mult<Health>>,
+            &Attribute<Regen<Health>>,
+            &Attribute<Max<Health>>,
+            &Attribute<Min<Health>>,
+        )>();
+        let (health, base, add, mult, regen, max, min) = sheet.iter(&world).next().unwrap();
+        assert_eq!(*health.amount(), Amount::from_num(42.5));
+        assert_eq!(*base.amount(), Amount::from_num(50));
+        assert_eq!(*add.amount(), Amount::from_num(5));
+        assert_eq!(*mult.amount(), Amount::from_num(1.25));
+        assert_eq!(*regen.amount(), Amount::from_num(2));
+        assert_eq!(*max.amount(), Amount::from_num(100));
+        assert_eq!(*min.amount(), Amount::ZERO);
+    }
 }
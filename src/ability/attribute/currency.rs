@@ -0,0 +1,369 @@
+use bevy::prelude::*;
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use super::attribute::{attribute_marker, Amount, Attribute, AttributeType, Max};
+use super::event::{change_attribute, AttributeChanged, ChangeCause};
+use super::plugin::{AttributeLabel, AttributePlugin};
+
+attribute_marker!(
+    /// Bonus to earned `A`, `0.1` is +10%. Below `-1` nothing is earned.
+    Gain,
+);
+
+/// Who a `Transaction<C>` moves currency between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransactionKind {
+    /// Currency entering the game, e.g. a bounty. Scaled by `Gain<C>` of the receiver.
+    Earn { to: Entity },
+    /// Currency leaving the game, e.g. buying from a shop.
+    Spend { from: Entity },
+    /// Currency moving between two entities, not scaled.
+    Transfer { from: Entity, to: Entity },
+}
+
+/// Request to change the balance of currency `C`.
+///
+/// Currencies should only change through transactions, every one of them is answered by a
+/// `LedgerEntry<C>` whether it succeeded or not. Credits stop at `Max<C>` of the receiver.
+#[derive(Debug)]
+pub struct Transaction<C> {
+    pub kind: TransactionKind,
+    pub amount: Amount,
+    /// What the transaction was for, e.g. `"shop"` or `"bounty"`.
+    pub reason: Option<String>,
+    phantom: PhantomData<C>,
+}
+
+impl<C> Clone for Transaction<C> {
+    fn clone(&self) -> Self {
+        Self {
+            kind: self.kind,
+            amount: self.amount,
+            reason: self.reason.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<C> Transaction<C> {
+    pub fn new(kind: TransactionKind, amount: Amount) -> Self {
+        Self {
+            kind: kind,
+            amount: amount,
+            reason: None,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn earn(to: Entity, amount: Amount) -> Self {
+        Self::new(TransactionKind::Earn { to: to }, amount)
+    }
+
+    pub fn spend(from: Entity, amount: Amount) -> Self {
+        Self::new(TransactionKind::Spend { from: from }, amount)
+    }
+
+    pub fn transfer(from: Entity, to: Entity, amount: Amount) -> Self {
+        Self::new(TransactionKind::Transfer { from: from, to: to }, amount)
+    }
+
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+}
+
+/// Why a `Transaction<C>` failed, nothing was changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionError {
+    /// The paying entity doesn't have enough.
+    InsufficientFunds { available: Amount, needed: Amount },
+    /// The entity has no `Attribute<C>`.
+    NoAccount(Entity),
+    /// Transactions can't have a negative amount.
+    NegativeAmount(Amount),
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionError::InsufficientFunds { available, needed } => {
+                write!(
+                    f,
+                    "insufficient funds, needed {} but had {}",
+                    needed, available
+                )
+            }
+            TransactionError::NoAccount(entity) => {
+                write!(f, "{:?} has no balance of this currency", entity)
+            }
+            TransactionError::NegativeAmount(amount) => {
+                write!(f, "transaction amount {} is negative", amount)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TransactionError {}
+
+/// Outcome of a single `Transaction<C>`.
+#[derive(Debug)]
+pub struct LedgerEntry<C> {
+    /// Sequential id of the transaction, per currency.
+    pub id: u64,
+    pub transaction: Transaction<C>,
+    /// The amount actually credited after `Gain<C>` and `Max<C>`.
+    pub result: Result<Amount, TransactionError>,
+}
+
+impl<C> Clone for LedgerEntry<C> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            transaction: self.transaction.clone(),
+            result: self.result,
+        }
+    }
+}
+
+type Accounts<'w, 's, C> = Query<
+    'w,
+    's,
+    (
+        &'static mut Attribute<C>,
+        Option<&'static Attribute<Gain<C>>>,
+        Option<&'static Attribute<Max<C>>>,
+    ),
+>;
+
+fn balance<C>(accounts: &Accounts<C>, entity: Entity) -> Result<Amount, TransactionError>
+where
+    C: 'static + Send + Sync,
+{
+    accounts
+        .get(entity)
+        .map(|(balance, _, _)| *balance.amount())
+        .map_err(|_| TransactionError::NoAccount(entity))
+}
+
+fn execute<C>(
+    events: &mut EventWriter<AttributeChanged<C>>,
+    accounts: &mut Accounts<C>,
+    transaction: &Transaction<C>,
+) -> Result<Amount, TransactionError>
+where
+    C: 'static + Send + Sync,
+{
+    let amount = transaction.amount;
+    if amount < Amount::ZERO {
+        return Err(TransactionError::NegativeAmount(amount));
+    }
+
+    let (from, to) = match transaction.kind {
+        TransactionKind::Earn { to } => (None, Some(to)),
+        TransactionKind::Spend { from } => (Some(from), None),
+        TransactionKind::Transfer { from, to } => (Some(from), Some(to)),
+    };
+
+    // Validate everything before touching any balance so failures change nothing.
+    if let Some(from) = from {
+        let available = balance(accounts, from)?;
+        if available < amount {
+            return Err(TransactionError::InsufficientFunds {
+                available: available,
+                needed: amount,
+            });
+        }
+    }
+    if let Some(to) = to {
+        balance(accounts, to)?;
+    }
+
+    let mut credited = match (transaction.kind, to) {
+        (TransactionKind::Earn { .. }, Some(to)) => match accounts.get(to) {
+            Ok((_, Some(gain), _)) => amount * (Amount::ONE + *gain.amount()).max(Amount::ZERO),
+            _ => amount,
+        },
+        _ => amount,
+    };
+
+    if let Some(from) = from {
+        let (mut balance, _, _) = accounts.get_mut(from).expect("validated above");
+        let result = *balance.amount() - amount;
+        change_attribute(events, from, &mut balance, result, ChangeCause::Transaction);
+    }
+    if let Some(to) = to {
+        let (mut balance, _, max) = accounts.get_mut(to).expect("validated above");
+        let old = *balance.amount();
+        let mut result = old + credited;
+        if let Some(max) = max {
+            // Credits never lower a balance that's already above its maximum.
+            result = result.min(old.max(*max.amount()));
+        }
+        credited = result - old;
+        change_attribute(events, to, &mut balance, result, ChangeCause::Transaction);
+    }
+
+    Ok(credited)
+}
+
+/// Applies every `Transaction<C>` in order, each one either fully succeeds or changes nothing.
+pub fn process_transactions<C>(
+    mut next_id: Local<u64>,
+    mut transactions: EventReader<Transaction<C>>,
+    mut ledger: EventWriter<LedgerEntry<C>>,
+    mut events: EventWriter<AttributeChanged<C>>,
+    mut accounts: Accounts<C>,
+) where
+    C: 'static + Send + Sync,
+{
+    for transaction in transactions.iter() {
+        let result = execute(&mut events, &mut accounts, transaction);
+        ledger.send(LedgerEntry {
+            id: *next_id,
+            transaction: transaction.clone(),
+            result: result,
+        });
+        *next_id += 1;
+    }
+}
+
+/// Registers the currency `C`, its `Gain<C>` and its transactions.
+///
+/// `C` is added with a plain `AttributePlugin`, so currencies shouldn't be given modifiers or
+/// regen, those would change balances without a `LedgerEntry<C>`.
+pub struct CurrencyPlugin<C>(PhantomData<C>);
+
+impl<C> Default for CurrencyPlugin<C> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<C> Plugin for CurrencyPlugin<C>
+where
+    C: AttributeType,
+{
+    fn build(&self, app: &mut App) {
+        app.add_plugin(AttributePlugin::<C>::default())
+            .register_type::<Attribute<Gain<C>>>()
+            .add_event::<Transaction<C>>()
+            .add_event::<LedgerEntry<C>>()
+            .add_system(process_transactions::<C>.before(AttributeLabel::clamp::<C>()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::app::Events;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct Gold;
+
+    #[test]
+    fn transactions() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(CurrencyPlugin::<Gold>::default());
+
+        let player = app
+            .world
+            .spawn()
+            .insert(Attribute::<Gold>::new(Amount::from_num(100)))
+            .insert(Attribute::<Gain<Gold>>::new(Amount::from_num(0.5)))
+            .id();
+        let shop = app
+            .world
+            .spawn()
+            .insert(Attribute::<Gold>::new(Amount::ZERO))
+            .id();
+
+        let mut transactions = app
+            .world
+            .get_resource_mut::<Events<Transaction<Gold>>>()
+            .unwrap();
+        transactions.send(Transaction::earn(player, Amount::from_num(300)).with_reason("bounty"));
+        transactions.send(Transaction::transfer(player, shop, Amount::from_num(600)));
+        transactions.send(Transaction::transfer(player, shop, Amount::from_num(200)));
+        app.update();
+
+        let ledger = app
+            .world
+            .get_resource::<Events<LedgerEntry<Gold>>>()
+            .unwrap();
+        let results = ledger
+            .get_reader()
+            .iter(ledger)
+            .map(|entry| (entry.id, entry.result))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            results,
+            vec![
+                (0, Ok(Amount::from_num(450))),
+                (
+                    1,
+                    Err(TransactionError::InsufficientFunds {
+                        available: Amount::from_num(550),
+                        needed: Amount::from_num(600),
+                    })
+                ),
+                (2, Ok(Amount::from_num(200))),
+            ]
+        );
+
+        let gold = |entity| *app.world.get::<Attribute<Gold>>(entity).unwrap().amount();
+        assert_eq!(gold(player), Amount::from_num(350));
+        assert_eq!(gold(shop), Amount::from_num(200));
+    }
+
+    #[test]
+    fn credits_are_capped() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(CurrencyPlugin::<Gold>::default());
+
+        let player = app
+            .world
+            .spawn()
+            .insert(Attribute::<Gold>::new(Amount::from_num(100)))
+            .insert(Attribute::<Max<Gold>>::new(Amount::from_num(150)))
+            .id();
+        let cursed = app
+            .world
+            .spawn()
+            .insert(Attribute::<Gold>::new(Amount::from_num(100)))
+            .insert(Attribute::<Gain<Gold>>::new(Amount::from_num(-2)))
+            .id();
+
+        let mut transactions = app
+            .world
+            .get_resource_mut::<Events<Transaction<Gold>>>()
+            .unwrap();
+        transactions.send(Transaction::earn(player, Amount::from_num(80)));
+        transactions.send(Transaction::transfer(cursed, player, Amount::from_num(50)));
+        transactions.send(Transaction::earn(cursed, Amount::from_num(80)));
+        app.update();
+
+        let ledger = app
+            .world
+            .get_resource::<Events<LedgerEntry<Gold>>>()
+            .unwrap();
+        let results = ledger
+            .get_reader()
+            .iter(ledger)
+            .map(|entry| entry.result)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            results,
+            vec![Ok(Amount::from_num(50)), Ok(Amount::ZERO), Ok(Amount::ZERO)]
+        );
+
+        let gold = |entity| *app.world.get::<Attribute<Gold>>(entity).unwrap().amount();
+        assert_eq!(gold(player), Amount::from_num(150));
+        assert_eq!(gold(cursed), Amount::from_num(50));
+    }
+}
@@ -0,0 +1,356 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use std::any::type_name;
+use std::fmt;
+use std::marker::PhantomData;
+
+use super::attribute::{short_type_name, Amount, Attribute, Base};
+use super::plugin::AttributeLabel;
+
+/// How a single source attribute contributes to a derived base.
+#[derive(Clone, Copy)]
+pub enum Term {
+    /// `coefficient * source`
+    Linear(Amount),
+    /// Any other function of the source.
+    Formula(fn(Amount) -> Amount),
+}
+
+impl Term {
+    pub fn apply(&self, source: Amount) -> Amount {
+        match self {
+            Term::Linear(coefficient) => *coefficient * source,
+            Term::Formula(formula) => formula(source),
+        }
+    }
+}
+
+impl fmt::Debug for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Term::Linear(coefficient) => write!(f, "Linear({})", coefficient),
+            Term::Formula(_) => write!(f, "Formula"),
+        }
+    }
+}
+
+/// Marks the `Base<A>` of an entity as derived from other attributes on the same entity.
+///
+/// Holds the current contribution of each source, which are summed into `Attribute<Base<A>>`
+/// together with the constant of the `DerivedAttributePlugin<A>`.
+#[derive(Component, Debug, Clone)]
+pub struct Derived<A> {
+    terms: HashMap<&'static str, Amount>,
+    phantom: PhantomData<A>,
+}
+
+impl<A> Default for Derived<A> {
+    fn default() -> Self {
+        Self {
+            terms: HashMap::default(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A> Derived<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn term<S>(&self) -> Option<Amount> {
+        self.terms.get(type_name::<S>()).copied()
+    }
+
+    pub fn total(&self) -> Amount {
+        self.terms
+            .values()
+            .fold(Amount::ZERO, |total, term| total + *term)
+    }
+
+    fn set_term<S>(&mut self, amount: Amount) {
+        self.terms.insert(type_name::<S>(), amount);
+    }
+}
+
+/// A derivation that would make an attribute depend on itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationCycle {
+    /// Attributes forming the cycle, the first attribute is repeated at the end.
+    pub path: Vec<String>,
+}
+
+impl fmt::Display for DerivationCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "derived attributes form a cycle: {}",
+            self.path.join(" -> ")
+        )
+    }
+}
+
+impl std::error::Error for DerivationCycle {}
+
+/// Which attributes each derived attribute is computed from.
+#[derive(Debug, Default)]
+pub struct AttributeDependencies {
+    sources: HashMap<&'static str, Vec<&'static str>>,
+    names: HashMap<&'static str, String>,
+}
+
+impl AttributeDependencies {
+    /// Records that `A` is derived from `S`, rejecting the dependency if `S` already
+    /// (transitively) depends on `A`.
+    pub fn add<A, S>(&mut self) -> Result<(), DerivationCycle> {
+        let derived = type_name::<A>();
+        let source = type_name::<S>();
+        self.names.insert(derived, short_type_name::<A>());
+        self.names.insert(source, short_type_name::<S>());
+
+        if let Some(mut path) = self.path(source, derived) {
+            path.insert(0, derived);
+            return Err(DerivationCycle {
+                path: path.iter().map(|name| self.names[name].clone()).collect(),
+            });
+        }
+
+        self.sources.entry(derived).or_default().push(source);
+        Ok(())
+    }
+
+    pub fn sources<A>(&self) -> &[&'static str] {
+        self.sources
+            .get(type_name::<A>())
+            .map(|sources| sources.as_slice())
+            .unwrap_or(&[])
+    }
+
+    // Dependency path from `from` to `to`, including both ends.
+    fn path(&self, from: &'static str, to: &'static str) -> Option<Vec<&'static str>> {
+        if from == to {
+            return Some(vec![to]);
+        }
+
+        for source in self.sources.get(from).into_iter().flatten() {
+            if let Some(mut path) = self.path(source, to) {
+                path.insert(0, from);
+                return Some(path);
+            }
+        }
+
+        None
+    }
+}
+
+/// Derives `Base<A>` from other attributes on the same entity.
+///
+/// The base becomes `constant + term(source) + ...` for every entity with a `Derived<A>`
+/// component, e.g. `Max<Health>` base = 100 + 10 × Strength:
+///
+/// ```ignore
+/// app.add_plugin(
+///     DerivedAttributePlugin::<Max<Health>>::new(Amount::from_num(100))
+///         .linear::<Strength>(Amount::from_num(10)),
+/// );
+/// ```
+///
+/// Each term runs after its source is fully resolved and the base is written before the
+/// modifiers of `A` run, so chains of derivations settle within a single frame. Sources
+/// should be registered with an `AttributePlugin`. Derivations forming a cycle panic when
+/// the plugin is added.
+pub struct DerivedAttributePlugin<A> {
+    constant: Amount,
+    terms: Vec<DerivedTerm>,
+    phantom: PhantomData<A>,
+}
+
+struct DerivedTerm {
+    term: Term,
+    register: fn(&mut App, Term),
+}
+
+impl<A> DerivedAttributePlugin<A>
+where
+    A: 'static + Send + Sync,
+{
+    pub fn new(constant: Amount) -> Self {
+        Self {
+            constant: constant,
+            terms: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn with<S>(mut self, term: Term) -> Self
+    where
+        S: 'static + Send + Sync,
+    {
+        self.terms.push(DerivedTerm {
+            term: term,
+            register: register_term::<A, S>,
+        });
+        self
+    }
+
+    pub fn linear<S>(self, coefficient: Amount) -> Self
+    where
+        S: 'static + Send + Sync,
+    {
+        self.with::<S>(Term::Linear(coefficient))
+    }
+
+    pub fn formula<S>(self, formula: fn(Amount) -> Amount) -> Self
+    where
+        S: 'static + Send + Sync,
+    {
+        self.with::<S>(Term::Formula(formula))
+    }
+}
+
+impl<A> Plugin for DerivedAttributePlugin<A>
+where
+    A: 'static + Send + Sync,
+{
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AttributeDependencies>();
+
+        for term in &self.terms {
+            (term.register)(app, term.term);
+        }
+
+        let constant = self.constant;
+        app.add_system(
+            (move |mut commands: Commands,
+                   mut derived: Query<
+                (Entity, &Derived<A>, Option<&mut Attribute<Base<A>>>),
+                Changed<Derived<A>>,
+            >| {
+                for (entity, derived, base) in derived.iter_mut() {
+                    let result = constant + derived.total();
+                    match base {
+                        Some(mut base) => {
+                            if *base.amount() != result {
+                                base.set_amount(result);
+                            }
+                        }
+                        None => {
+                            commands
+                                .entity(entity)
+                                .insert(Attribute::<Base<A>>::new(result));
+                        }
+                    }
+                }
+            })
+            .label(AttributeLabel::derive::<A>())
+            .after(AttributeLabel::derive_terms::<A>())
+            .before(AttributeLabel::aggregate::<A>())
+            .before(AttributeLabel::modifiers::<A>()),
+        );
+    }
+}
+
+fn register_term<A, S>(app: &mut App, term: Term)
+where
+    A: 'static + Send + Sync,
+    S: 'static + Send + Sync,
+{
+    if let Err(cycle) = app
+        .world
+        .get_resource_mut::<AttributeDependencies>()
+        .expect("AttributeDependencies should be initialized by the DerivedAttributePlugin")
+        .add::<A, S>()
+    {
+        panic!("{}", cycle);
+    }
+
+    app.add_system(
+        (move |mut derived: Query<
+            (&Attribute<S>, &mut Derived<A>),
+            Or<(Changed<Attribute<S>>, Added<Derived<A>>)>,
+        >| {
+            for (source, mut derived) in derived.iter_mut() {
+                let amount = term.apply(*source.amount());
+                if derived.term::<S>() != Some(amount) {
+                    derived.set_term::<S>(amount);
+                }
+            }
+        })
+        .label(AttributeLabel::derive_terms::<A>())
+        .after(AttributeLabel::clamp::<S>())
+        .before(AttributeLabel::derive::<A>()),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::attribute::Max;
+    use crate::ability::attribute::plugin::AttributePlugin;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct Health;
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct Strength;
+
+    #[test]
+    fn derived_max_health() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<Strength>::default())
+            .add_plugin(AttributePlugin::<Health>::default())
+            .add_plugin(
+                DerivedAttributePlugin::<Max<Health>>::new(Amount::from_num(100))
+                    .linear::<Strength>(Amount::from_num(10)),
+            );
+
+        let player = app
+            .world
+            .spawn()
+            .insert(Attribute::<Strength>::new(Amount::from_num(5)))
+            .insert(Attribute::<Max<Health>>::new(Amount::ZERO))
+            .insert(Attribute::<Base<Max<Health>>>::new(Amount::ZERO))
+            .insert(Derived::<Max<Health>>::new())
+            .id();
+
+        app.update();
+        assert_eq!(
+            *app.world
+                .get::<Attribute<Max<Health>>>(player)
+                .unwrap()
+                .amount(),
+            Amount::from_num(150)
+        );
+
+        app.world
+            .get_mut::<Attribute<Strength>>(player)
+            .unwrap()
+            .set_amount(Amount::from_num(10));
+        app.update();
+        assert_eq!(
+            *app.world
+                .get::<Attribute<Max<Health>>>(player)
+                .unwrap()
+                .amount(),
+            Amount::from_num(200)
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "derived attributes form a cycle: Strength -> Max<Health> -> Strength"
+    )]
+    fn derived_cycle() {
+        let mut app = App::new();
+        app.add_plugin(
+            DerivedAttributePlugin::<Max<Health>>::new(Amount::ZERO)
+                .linear::<Strength>(Amount::ONE),
+        )
+        .add_plugin(
+            DerivedAttributePlugin::<Strength>::new(Amount::ZERO)
+                .linear::<Max<Health>>(Amount::ONE),
+        );
+    }
+}
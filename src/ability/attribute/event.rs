@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+
+use std::marker::PhantomData;
+
+use super::attribute::{Amount, Attribute};
+
+/// What caused an attribute to change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeCause {
+    /// Recomputed from its base and modifiers by `basic_modifiers`.
+    Modifiers,
+    /// Clamped to its `Min<A>` or `Max<A>`.
+    Clamp,
+    /// Regenerated by `regen`.
+    Regen,
+    /// Adjusted to a change of its `Max<A>`, see `MaxChangePolicy`.
+    MaxChanged,
+    /// Damaged, by the given entity if known.
+    Damage(Option<Entity>),
+    /// Healed through a `HealEvent<A>`, by the given entity if known.
+    Heal(Option<Entity>),
+    /// Earned, spent or transferred through a `Transaction<C>`.
+    Transaction,
+    /// Gained through a `GainExperience`, for `Xp` and `Level`.
+    Experience,
+}
+
+/// Sent whenever one of forte's systems changes the value of an `Attribute<A>`.
+#[derive(Debug)]
+pub struct AttributeChanged<A> {
+    pub entity: Entity,
+    pub old: Amount,
+    pub new: Amount,
+    /// `new - old`
+    pub delta: Amount,
+    pub cause: ChangeCause,
+    phantom: PhantomData<A>,
+}
+
+impl<A> Clone for AttributeChanged<A> {
+    fn clone(&self) -> Self {
+        Self {
+            entity: self.entity,
+            old: self.old,
+            new: self.new,
+            delta: self.delta,
+            cause: self.cause,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A> AttributeChanged<A> {
+    pub fn new(entity: Entity, old: Amount, new: Amount, cause: ChangeCause) -> Self {
+        Self {
+            entity: entity,
+            old: old,
+            new: new,
+            delta: new - old,
+            cause: cause,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Sets the amount of `attribute`, sending an `AttributeChanged<A>` if the value actually
+/// changed.
+///
+/// Returns whether the value changed. Change detection of the attribute is only triggered
+/// when it did.
+pub fn change_attribute<A>(
+    events: &mut EventWriter<AttributeChanged<A>>,
+    entity: Entity,
+    attribute: &mut Mut<Attribute<A>>,
+    amount: Amount,
+    cause: ChangeCause,
+) -> bool
+where
+    A: 'static + Send + Sync,
+{
+    let old = *attribute.amount();
+    if old == amount {
+        return false;
+    }
+
+    attribute.set_amount(amount);
+    events.send(AttributeChanged::new(entity, old, amount, cause));
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::attribute::{Base, Max};
+    use crate::ability::attribute::plugin::AttributePlugin;
+    use bevy::app::Events;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct Health;
+
+    #[test]
+    fn changed_events() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<Health>::default());
+
+        let player = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::ZERO))
+            .insert(Attribute::<Base<Health>>::new(Amount::from_num(12)))
+            .insert(Attribute::<Max<Health>>::new(Amount::from_num(10)))
+            .id();
+
+        app.update();
+
+        let events = app
+            .world
+            .get_resource::<Events<AttributeChanged<Health>>>()
+            .unwrap();
+        let changes = events
+            .get_reader()
+            .iter(events)
+            .map(|event| (event.entity, event.old, event.new, event.delta, event.cause))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            vec![
+                (
+                    player,
+                    Amount::ZERO,
+                    Amount::from_num(12),
+                    Amount::from_num(12),
+                    ChangeCause::Modifiers
+                ),
+                (
+                    player,
+                    Amount::from_num(12),
+                    Amount::from_num(10),
+                    Amount::from_num(-2),
+                    ChangeCause::Clamp
+                ),
+            ]
+        );
+    }
+}
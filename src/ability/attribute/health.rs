@@ -1,149 +1,245 @@
 use bevy::prelude::*;
+
 use std::collections::VecDeque;
-use std::marker::PhantomData;
-use vec_collections::VecMap;
 
+pub use super::attribute::Health;
+use super::attribute::{Amount, Attribute, Base, Max};
+use super::event::{AttributeChanged, ChangeCause};
+use super::plugin::{AttributeLabel, AttributePlugin};
+use super::regen::{Regen, RegenMode};
+
+/// A single decrease of an entity's `Attribute<Health>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Damage {
+    /// Who dealt the damage, `None` if the decrease wasn't attributed to anyone.
+    pub from: Option<Entity>,
+    pub amount: Amount,
+    pub cause: ChangeCause,
+    /// `Time::seconds_since_startup` when the damage was taken.
+    pub time: f64,
+}
+
+/// Most recent decreases of an entity's `Attribute<Health>`, oldest first.
+///
+/// Every decrease forte makes is recorded, the source is only known for
+/// `ChangeCause::Damage(Some(from))`.
 #[derive(Component, Debug, Clone)]
 pub struct DamageHistory {
     history: VecDeque<Damage>,
+    capacity: usize,
 }
 
-impl DamageHistory {
-    fn push(&mut self, damage: Damage) {
-        self.history.push_back(damage);
+impl Default for DamageHistory {
+    fn default() -> Self {
+        Self::new(32)
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Damage {
-    pub from: Entity,
-    pub amount: u32,
-    //pub ability: Entity,
-}
+impl DamageHistory {
+    /// History keeping at most `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            history: VecDeque::with_capacity(capacity),
+            capacity: capacity,
+        }
+    }
 
-#[derive(Component, Debug, Clone, Default)]
-pub struct Health {
-    amount: u32,
-}
+    pub fn push(&mut self, damage: Damage) {
+        if self.capacity == 0 {
+            return;
+        }
 
-impl Health {
-    pub fn new(health: u32) -> Self {
-        Self { amount: health }
+        if self.history.len() == self.capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(damage);
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Damage> {
+        self.history.iter()
     }
 
-    pub fn damage(&mut self, time: &Time, history: &mut DamageHistory, damage: Damage) {
-        self.amount = self.amount.saturating_sub(damage.amount);
-        history.push(damage);
+    pub fn last(&self) -> Option<&Damage> {
+        self.history.back()
     }
 
-    pub fn amount(&self) -> u32 {
-        self.amount
+    /// Last entity that damaged this one, e.g. for kill credit.
+    pub fn last_attacker(&self) -> Option<Entity> {
+        self.history.iter().rev().find_map(|damage| damage.from)
     }
 
-    pub fn set_amount(&mut self, amount: u32) {
-        self.amount = amount;
+    /// Total damage dealt by `from` still in the history.
+    pub fn total_from(&self, from: Entity) -> Amount {
+        self.history
+            .iter()
+            .filter(|damage| damage.from == Some(from))
+            .map(|damage| damage.amount)
+            .sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
     }
 }
 
-#[derive(Component, Debug, Clone, Default)]
-pub struct MaxHealth(u32);
+/// Records every decrease of `Attribute<Health>` into the entity's `DamageHistory`.
+pub fn record_damage(
+    time: Res<Time>,
+    mut changes: EventReader<AttributeChanged<Health>>,
+    mut histories: Query<&mut DamageHistory>,
+) {
+    for change in changes.iter() {
+        if change.delta >= Amount::ZERO {
+            continue;
+        }
 
-#[derive(Component, Debug, Clone, Default)]
-pub struct HealthRegen(u32);
+        if let Ok(mut history) = histories.get_mut(change.entity) {
+            let from = match change.cause {
+                ChangeCause::Damage(from) => from,
+                _ => None,
+            };
+
+            history.push(Damage {
+                from: from,
+                amount: -change.delta,
+                cause: change.cause,
+                time: time.seconds_since_startup(),
+            });
+        }
+    }
+}
 
-pub fn regen(mut query: Query<(&mut Health, &MaxHealth, &HealthRegen)>) {
-    for (mut health, max_health, regen) in query.iter_mut() {
-        let current = health.amount();
-        let max = max_health.0;
-        let regen = regen.0;
-        if current > 0 && current < max {
-            health.set_amount(max.min(current + regen));
+/// Everything an entity needs to have health.
+#[derive(Bundle, Default)]
+pub struct HealthBundle {
+    pub health: Attribute<Health>,
+    pub max_health: Attribute<Max<Health>>,
+    pub base_max_health: Attribute<Base<Max<Health>>>,
+    pub regen: Attribute<Regen<Health>>,
+    pub history: DamageHistory,
+}
+
+impl HealthBundle {
+    /// Full health of `max`, regenerating `regen` per second.
+    pub fn new(max: Amount, regen: Amount) -> Self {
+        Self {
+            health: Attribute::new(max),
+            max_health: Attribute::new(max),
+            base_max_health: Attribute::new(max),
+            regen: Attribute::new(regen),
+            history: DamageHistory::default(),
         }
     }
 }
 
+/// `AttributePlugin<Health>` plus the `DamageHistory` bookkeeping.
+///
+/// Health uses `RegenMode::UnlessZero`, dead entities don't regenerate back to life.
+#[derive(Default)]
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(AttributePlugin::<Health>::new().with_regen_mode(RegenMode::UnlessZero))
+            .add_system(record_damage.after(AttributeLabel::thresholds::<Health>()));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use bevy::core::FixedTimestep;
+    use crate::ability::attribute::event::change_attribute;
+    use crate::ability::attribute::regen::RegenClock;
+
     use std::time::Duration;
 
+    struct Attack {
+        target: Entity,
+        from: Entity,
+        amount: Amount,
+    }
+
+    fn attack(
+        mut attacks: ResMut<Vec<Attack>>,
+        mut events: EventWriter<AttributeChanged<Health>>,
+        mut health: Query<&mut Attribute<Health>>,
+    ) {
+        for attack in attacks.drain(..) {
+            let mut current = health.get_mut(attack.target).unwrap();
+            let result = *current.amount() - attack.amount;
+            change_attribute(
+                &mut events,
+                attack.target,
+                &mut current,
+                result,
+                ChangeCause::Damage(Some(attack.from)),
+            );
+        }
+    }
+
     #[test]
-    fn health_regen() {
+    fn health_regen_and_history() {
         let mut app = App::new();
-        app.add_plugins(MinimalPlugins).add_system_to_stage(
-            CoreStage::Update,
-            regen
-                .system()
-                .with_run_criteria(FixedTimestep::step(0.6).with_label("100bpm")),
-        );
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(HealthPlugin)
+            .insert_resource(RegenClock::fixed(Duration::from_secs(1)))
+            .insert_resource(Vec::<Attack>::new())
+            .add_system(attack.before(AttributeLabel::regen::<Health>()));
 
+        let attacker = app.world.spawn().id();
         let player = app
             .world
             .spawn()
-            .insert(Health::new(1))
-            .insert(MaxHealth(10))
-            .insert(HealthRegen(5))
-            .id();
-
-        let dead_player = app
-            .world
-            .spawn()
-            .insert(Health::new(0))
-            .insert(MaxHealth(10))
-            .insert(HealthRegen(5))
+            .insert_bundle(HealthBundle::new(Amount::from_num(10), Amount::from_num(5)))
             .id();
-
-        assert_eq!(app.world.get::<Health>(player).unwrap().amount(), 1);
-        assert_eq!(app.world.get::<Health>(dead_player).unwrap().amount(), 0);
+        let health = |app: &App| *app.world.get::<Attribute<Health>>(player).unwrap().amount();
 
         app.update();
-        assert_eq!(app.world.get::<Health>(player).unwrap().amount(), 1);
-
-        let bpm = Duration::from_millis(600); // 0.6 seconds is 100bpm
-        std::thread::sleep(bpm);
+        assert_eq!(health(&app), Amount::from_num(10));
+
+        app.world
+            .get_resource_mut::<Vec<Attack>>()
+            .unwrap()
+            .push(Attack {
+                target: player,
+                from: attacker,
+                amount: Amount::from_num(9),
+            });
         app.update();
-        assert_eq!(app.world.get::<Health>(player).unwrap().amount(), 6);
+        assert_eq!(health(&app), Amount::from_num(6));
 
-        std::thread::sleep(bpm);
         app.update();
+        assert_eq!(health(&app), Amount::from_num(10));
 
-        assert_eq!(app.world.get::<Health>(player).unwrap().amount(), 10);
-        assert_eq!(app.world.get::<Health>(dead_player).unwrap().amount(), 0);
+        let history = app.world.get::<DamageHistory>(player).unwrap();
+        assert_eq!(history.iter().count(), 1);
+        assert_eq!(history.last_attacker(), Some(attacker));
+        assert_eq!(history.total_from(attacker), Amount::from_num(9));
     }
-}
 
-/*
-attack_damage : Attack Damage,
-ability_power : Ability Power,
-armor : Armor,
-magic_resist : Magic Resist,
-health_max : Health,
-resource_max : Resource,
-health_regen : Health Regen,
-resource_regen : Resource Regen,
-movement_speed : Movement Speed,
-magic_pen : Magic Penatration,
-phys_pen : Physical Penatration,
-
-cdr : Cooldown Reduction,
-crit : Critical Strike Chance,
-crit_damage : Critical Strike Damage,
-attack_speed : Attack Speed,
-lifesteal : Lifesteal,
-spell_vamp : Spell Vamp,
-
-heal_power : Healing Power,
-shield_power : Shield Power,
-tenacity : Tenacity,
-
-damage_reduction : Damage Reduction,
-damage_dealt : Damage Dealt,
-damage_reflect : Damaged Reflect,
-
-xp : XP,
-xpgain : XP Gain,
-gold : Gold,
-goldgain : Gold Gain,
-level : Level,
-*/
+    #[test]
+    fn dead_player() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(HealthPlugin)
+            .insert_resource(RegenClock::fixed(Duration::from_secs(1)));
+
+        let player = app
+            .world
+            .spawn()
+            .insert_bundle(HealthBundle::new(Amount::from_num(10), Amount::from_num(5)))
+            .id();
+        app.world
+            .get_mut::<Attribute<Health>>(player)
+            .unwrap()
+            .set_amount(Amount::ZERO);
+
+        for _ in 0..3 {
+            app.update();
+            assert_eq!(
+                *app.world.get::<Attribute<Health>>(player).unwrap().amount(),
+                Amount::ZERO
+            );
+        }
+    }
+}
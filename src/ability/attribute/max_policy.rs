@@ -0,0 +1,213 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use std::marker::PhantomData;
+
+use super::attribute::{Amount, Attribute, Max};
+use super::event::{change_attribute, AttributeChanged, ChangeCause};
+
+/// What happens to the current value of `A` when `Max<A>` changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MaxChangePolicy {
+    /// The current value is left alone and only clamped if it is above the new max.
+    KeepAbsolute,
+    /// The current value keeps the same fraction of the max, 50/100 becomes 100/200.
+    KeepRatio,
+    /// Increases of the max are added to the current value, 50/100 becomes 150/200.
+    /// Decreases only clamp.
+    GrantIncrease,
+}
+
+impl Default for MaxChangePolicy {
+    fn default() -> Self {
+        MaxChangePolicy::KeepAbsolute
+    }
+}
+
+impl MaxChangePolicy {
+    pub fn apply(&self, current: Amount, old_max: Amount, new_max: Amount) -> Amount {
+        match self {
+            MaxChangePolicy::KeepAbsolute => current,
+            MaxChangePolicy::KeepRatio => {
+                if old_max == Amount::ZERO {
+                    current
+                } else {
+                    current * new_max / old_max
+                }
+            }
+            MaxChangePolicy::GrantIncrease => {
+                if new_max > old_max {
+                    current + (new_max - old_max)
+                } else {
+                    current
+                }
+            }
+        }
+    }
+}
+
+/// `MaxChangePolicy` of the attribute `A`.
+#[derive(Debug)]
+pub struct MaxPolicy<A> {
+    pub policy: MaxChangePolicy,
+    phantom: PhantomData<A>,
+}
+
+impl<A> MaxPolicy<A> {
+    pub fn new(policy: MaxChangePolicy) -> Self {
+        Self {
+            policy: policy,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A> Default for MaxPolicy<A> {
+    fn default() -> Self {
+        Self::new(MaxChangePolicy::default())
+    }
+}
+
+/// Value of `Max<A>` when `apply_max_policy` last saw it, inserted the first time it does.
+#[derive(Component, Debug)]
+pub struct PreviousMax<A> {
+    amount: Amount,
+    phantom: PhantomData<A>,
+}
+
+impl<A> PreviousMax<A> {
+    pub fn new(amount: Amount) -> Self {
+        Self {
+            amount: amount,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn amount(&self) -> Amount {
+        self.amount
+    }
+}
+
+/// Applies the `MaxPolicy<A>` whenever an entity's `Max<A>` changes, whether forte changed it
+/// or it was edited directly.
+///
+/// The old max is kept in `PreviousMax<A>`, the first max seen for an entity is only recorded.
+/// Runs before `A` is clamped, so with `MaxChangePolicy::KeepAbsolute` the clamp is the
+/// only thing that touches the current value.
+pub fn apply_max_policy<A>(
+    mut commands: Commands,
+    policy: Res<MaxPolicy<A>>,
+    mut events: EventWriter<AttributeChanged<A>>,
+    mut attributes: Query<
+        (
+            Entity,
+            &Attribute<Max<A>>,
+            Option<&mut PreviousMax<A>>,
+            Option<&mut Attribute<A>>,
+        ),
+        Changed<Attribute<Max<A>>>,
+    >,
+) where
+    A: 'static + Send + Sync,
+{
+    for (entity, max, previous, current) in attributes.iter_mut() {
+        let new_max = *max.amount();
+        let mut previous = match previous {
+            Some(previous) => previous,
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(PreviousMax::<A>::new(new_max));
+                continue;
+            }
+        };
+
+        let old_max = previous.amount;
+        if old_max == new_max {
+            continue;
+        }
+        previous.amount = new_max;
+
+        if let Some(mut current) = current {
+            let result = policy.policy.apply(*current.amount(), old_max, new_max);
+            change_attribute(
+                &mut events,
+                entity,
+                &mut current,
+                result,
+                ChangeCause::MaxChanged,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::attribute::Base;
+    use crate::ability::attribute::plugin::AttributePlugin;
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct Health;
+
+    fn level_up(policy: MaxChangePolicy) -> Amount {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<Health>::new().with_max_policy(policy));
+
+        let player = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(50)))
+            .insert(Attribute::<Max<Health>>::new(Amount::from_num(100)))
+            .insert(Attribute::<Base<Max<Health>>>::new(Amount::from_num(100)))
+            .id();
+        app.update();
+
+        app.world
+            .get_mut::<Attribute<Base<Max<Health>>>>(player)
+            .unwrap()
+            .set_amount(Amount::from_num(200));
+        app.update();
+
+        *app.world.get::<Attribute<Health>>(player).unwrap().amount()
+    }
+
+    #[test]
+    fn direct_max_edit() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins).add_plugin(
+            AttributePlugin::<Health>::new().with_max_policy(MaxChangePolicy::KeepRatio),
+        );
+
+        let player = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(50)))
+            .insert(Attribute::<Max<Health>>::new(Amount::from_num(100)))
+            .id();
+        app.update();
+
+        app.world
+            .get_mut::<Attribute<Max<Health>>>(player)
+            .unwrap()
+            .set_amount(Amount::from_num(300));
+        app.update();
+
+        let health = app.world.get::<Attribute<Health>>(player).unwrap();
+        assert_eq!(*health.amount(), Amount::from_num(150));
+    }
+
+    #[test]
+    fn max_change_policies() {
+        assert_eq!(
+            level_up(MaxChangePolicy::KeepAbsolute),
+            Amount::from_num(50)
+        );
+        assert_eq!(level_up(MaxChangePolicy::KeepRatio), Amount::from_num(100));
+        assert_eq!(
+            level_up(MaxChangePolicy::GrantIncrease),
+            Amount::from_num(150)
+        );
+    }
+}
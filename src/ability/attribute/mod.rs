@@ -1,3 +1,19 @@
+pub mod amount;
 pub mod attribute;
+pub mod currency;
+pub mod derived;
+pub mod event;
 pub mod health;
+pub mod max_policy;
+pub mod modifier;
+pub mod pipeline;
+pub mod plugin;
+pub mod progression;
 pub mod regen;
+pub mod registry;
+pub mod snapshot;
+pub mod soft_cap;
+#[cfg(feature = "standard-stats")]
+pub mod standard;
+pub mod stat_sheet;
+pub mod threshold;
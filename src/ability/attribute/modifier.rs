@@ -0,0 +1,238 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use std::marker::PhantomData;
+
+use super::attribute::{Amount, Attribute};
+use super::pipeline::{AttributePipeline, PercentStacking};
+use crate::effect::EffectTarget;
+
+/// A single source's contribution to `A`.
+///
+/// Modifiers live on their own entity (a buff, an item, an aura) and apply to the entity in
+/// their `EffectTarget`. Every modifier targeting the same entity is combined into that entity's
+/// `ModifierTotals<A>`, the percentages either added up or multiplied depending on the
+/// `PercentStacking` of `AttributePipeline<A>`, so two buffs to the same attribute stack
+/// instead of overwriting each other.
+#[derive(Component, Debug, Clone)]
+pub struct Modifier<A> {
+    /// Flat amount added to `A`.
+    pub flat: Amount,
+    /// Percentage bonus to `A`, `0.1` is +10%.
+    pub percent: Amount,
+    phantom: PhantomData<A>,
+}
+
+impl<A> Modifier<A> {
+    pub fn new(flat: Amount, percent: Amount) -> Self {
+        Self {
+            flat: flat,
+            percent: percent,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn flat(flat: Amount) -> Self {
+        Self::new(flat, Amount::ZERO)
+    }
+
+    pub fn percent(percent: Amount) -> Self {
+        Self::new(Amount::ZERO, percent)
+    }
+}
+
+/// Combined `Modifier<A>`s targeting an entity, written by `aggregate_modifiers`.
+///
+/// Kept apart from the `Attribute<Add<A>>`, `Attribute<Percent<A>>` and `Attribute<Mult<A>>`
+/// set by hand, `basic_modifiers` adds `flat` to `Add<A>`, `percent` to `Percent<A>` and
+/// multiplies `Mult<A>` by `mult`.
+#[derive(Component, Debug)]
+pub struct ModifierTotals<A> {
+    pub flat: Amount,
+    pub percent: Amount,
+    pub mult: Amount,
+    phantom: PhantomData<A>,
+}
+
+impl<A> Default for ModifierTotals<A> {
+    fn default() -> Self {
+        Self {
+            flat: Amount::ZERO,
+            percent: Amount::ZERO,
+            mult: Amount::ONE,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A> Clone for ModifierTotals<A> {
+    fn clone(&self) -> Self {
+        Self {
+            flat: self.flat,
+            percent: self.percent,
+            mult: self.mult,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A> PartialEq for ModifierTotals<A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.flat == other.flat && self.percent == other.percent && self.mult == other.mult
+    }
+}
+
+/// Gives the targets of new `Modifier<A>`s their `ModifierTotals<A>`.
+///
+/// Runs in `CoreStage::PreUpdate`, so the totals already exist when `aggregate_modifiers` runs
+/// and the first modifier of a target applies in the frame it was added.
+pub fn insert_modifier_totals<A>(
+    mut commands: Commands,
+    modifiers: Query<&EffectTarget, Added<Modifier<A>>>,
+    targets: Query<(), Without<ModifierTotals<A>>>,
+) where
+    A: 'static + Send + Sync,
+{
+    for target in modifiers.iter() {
+        if targets.get(target.entity()).is_ok() {
+            commands
+                .entity(target.entity())
+                .insert(ModifierTotals::<A>::default());
+        }
+    }
+}
+
+/// Combines every `Modifier<A>` into the `ModifierTotals<A>` of its target.
+///
+/// This recomputes every frame rather than relying on `RemovedComponents`, since modifier
+/// entities are usually despawned in `CoreStage::Last` after removals have been cleared.
+/// The totals are only written when the result actually differs, so `basic_modifiers` still
+/// only runs when something changed. Targets which lose all of their modifiers keep neutral
+/// totals.
+pub fn aggregate_modifiers<A>(
+    pipeline: Option<Res<AttributePipeline<A>>>,
+    modifiers: Query<(&Modifier<A>, &EffectTarget)>,
+    mut totals: Query<(Entity, &mut ModifierTotals<A>)>,
+) where
+    A: 'static + Send + Sync,
+{
+    let stacking = pipeline
+        .map(|pipeline| pipeline.percent_stacking)
+        .unwrap_or(PercentStacking::Additive);
+
+    let mut aggregates: HashMap<Entity, ModifierTotals<A>> = HashMap::default();
+    for (modifier, target) in modifiers.iter() {
+        let aggregate = aggregates.entry(target.entity()).or_default();
+        aggregate.flat += modifier.flat;
+        match stacking {
+            PercentStacking::Additive => aggregate.percent += modifier.percent,
+            PercentStacking::Multiplicative => aggregate.mult *= Amount::ONE + modifier.percent,
+        }
+    }
+
+    for (entity, mut current) in totals.iter_mut() {
+        let aggregate = aggregates.remove(&entity).unwrap_or_default();
+        if *current != aggregate {
+            *current = aggregate;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::attribute::{Add, Base};
+    use crate::ability::attribute::plugin::AttributePlugin;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct MovementSpeed;
+
+    #[test]
+    fn modifiers_stack() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<MovementSpeed>::default());
+
+        let target = app
+            .world
+            .spawn()
+            .insert(Attribute::<MovementSpeed>::new(Amount::ZERO))
+            .insert(Attribute::<Base<MovementSpeed>>::new(Amount::from_num(100)))
+            .id();
+
+        let boots = app
+            .world
+            .spawn()
+            .insert(EffectTarget(target))
+            .insert(Modifier::<MovementSpeed>::flat(Amount::from_num(25)))
+            .id();
+
+        let _haste = app
+            .world
+            .spawn()
+            .insert(EffectTarget(target))
+            .insert(Modifier::<MovementSpeed>::percent(Amount::from_num(0.5)))
+            .id();
+
+        app.update();
+        assert_eq!(
+            *app.world
+                .get::<Attribute<MovementSpeed>>(target)
+                .unwrap()
+                .amount(),
+            Amount::from_num(187.5)
+        );
+
+        app.world.despawn(boots);
+        app.update();
+        assert_eq!(
+            *app.world
+                .get::<Attribute<MovementSpeed>>(target)
+                .unwrap()
+                .amount(),
+            Amount::from_num(150)
+        );
+    }
+
+    #[test]
+    fn hand_set_modifiers_survive() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<MovementSpeed>::default());
+
+        let target = app
+            .world
+            .spawn()
+            .insert(Attribute::<MovementSpeed>::new(Amount::ZERO))
+            .insert(Attribute::<Base<MovementSpeed>>::new(Amount::from_num(100)))
+            .insert(Attribute::<Add<MovementSpeed>>::new(Amount::from_num(10)))
+            .id();
+        let boots = app
+            .world
+            .spawn()
+            .insert(EffectTarget(target))
+            .insert(Modifier::<MovementSpeed>::flat(Amount::from_num(25)))
+            .id();
+
+        app.update();
+        let speed = |app: &App| {
+            *app.world
+                .get::<Attribute<MovementSpeed>>(target)
+                .unwrap()
+                .amount()
+        };
+        assert_eq!(speed(&app), Amount::from_num(135));
+
+        app.world.despawn(boots);
+        app.update();
+        assert_eq!(speed(&app), Amount::from_num(110));
+        assert_eq!(
+            *app.world
+                .get::<Attribute<Add<MovementSpeed>>>(target)
+                .unwrap()
+                .amount(),
+            Amount::from_num(10)
+        );
+    }
+}
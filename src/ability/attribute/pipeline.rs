@@ -0,0 +1,190 @@
+use std::marker::PhantomData;
+
+use super::attribute::Amount;
+use super::soft_cap::SoftCap;
+
+/// A single step of an attribute's modifier pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModifierStage {
+    /// `value + Add<A>`
+    Add,
+    /// `value * (1 + Percent<A>)`, percentages are summed before being applied.
+    Percent,
+    /// `value * Mult<A>`, multipliers are multiplied with each other before being applied.
+    Mult,
+    /// `value - Sub<A>`
+    Sub,
+    /// `Override<A>` replaces the value entirely if present.
+    Override,
+}
+
+/// How the `percent` of multiple `Modifier<A>`s on the same target combine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PercentStacking {
+    /// Percentages are summed into `Percent<A>`, two +50% modifiers give +100%.
+    Additive,
+    /// Percentages are multiplied into `Mult<A>`, two +50% modifiers give +125%.
+    Multiplicative,
+}
+
+/// Values of the modifier components of a single entity, neutral values when missing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModifierValues {
+    pub add: Amount,
+    pub percent: Amount,
+    pub mult: Amount,
+    pub sub: Amount,
+    pub overridden: Option<Amount>,
+}
+
+impl Default for ModifierValues {
+    fn default() -> Self {
+        Self {
+            add: Amount::ZERO,
+            percent: Amount::ZERO,
+            mult: Amount::ONE,
+            sub: Amount::ZERO,
+            overridden: None,
+        }
+    }
+}
+
+/// Order in which `basic_modifiers` folds the modifier components of `A` into its value.
+///
+/// The default pipeline is:
+///
+/// `Base<A>` → `Add<A>` → `Percent<A>` → `Mult<A>` → `Sub<A>` → `Override<A>` → clamps
+///
+/// Percentages apply to the base plus flat bonuses and `Sub<A>` is taken off after all
+/// scaling. Moving `ModifierStage::Percent` in front of `ModifierStage::Add` makes
+/// percentages only scale the base. `SoftCap`s are applied in order after every stage. The
+/// clamps against `Min<A>`/`Max<A>` always run last as their own systems.
+#[derive(Debug)]
+pub struct AttributePipeline<A> {
+    pub stages: Vec<ModifierStage>,
+    pub percent_stacking: PercentStacking,
+    pub soft_caps: Vec<SoftCap>,
+    phantom: PhantomData<A>,
+}
+
+impl<A> Clone for AttributePipeline<A> {
+    fn clone(&self) -> Self {
+        Self {
+            stages: self.stages.clone(),
+            percent_stacking: self.percent_stacking,
+            soft_caps: self.soft_caps.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A> Default for AttributePipeline<A> {
+    fn default() -> Self {
+        Self::new(vec![
+            ModifierStage::Add,
+            ModifierStage::Percent,
+            ModifierStage::Mult,
+            ModifierStage::Sub,
+            ModifierStage::Override,
+        ])
+    }
+}
+
+impl<A> AttributePipeline<A> {
+    pub fn new(stages: Vec<ModifierStage>) -> Self {
+        Self {
+            stages: stages,
+            percent_stacking: PercentStacking::Additive,
+            soft_caps: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn with_percent_stacking(mut self, stacking: PercentStacking) -> Self {
+        self.percent_stacking = stacking;
+        self
+    }
+
+    /// Adds a soft cap applied after all previously added ones.
+    pub fn with_soft_cap(mut self, soft_cap: SoftCap) -> Self {
+        self.soft_caps.push(soft_cap);
+        self
+    }
+
+    /// Applies every stage to `base` in order, then the soft caps.
+    ///
+    /// Saturates at `Amount::MIN`/`Amount::MAX`, so stacking large multipliers can't overflow.
+    pub fn evaluate(&self, base: Amount, values: &ModifierValues) -> Amount {
+        let mut value = base;
+        for stage in &self.stages {
+            value = match stage {
+                ModifierStage::Add => value.saturating_add(values.add),
+                ModifierStage::Percent => {
+                    value.saturating_mul(Amount::ONE.saturating_add(values.percent))
+                }
+                ModifierStage::Mult => value.saturating_mul(values.mult),
+                ModifierStage::Sub => value.saturating_sub(values.sub),
+                ModifierStage::Override => values.overridden.unwrap_or(value),
+            };
+        }
+
+        self.soft_caps
+            .iter()
+            .fold(value, |value, soft_cap| soft_cap.apply(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Armor;
+
+    #[test]
+    fn pipeline_order() {
+        let values = ModifierValues {
+            add: Amount::from_num(10),
+            percent: Amount::from_num(0.5),
+            mult: Amount::from_num(2),
+            sub: Amount::from_num(5),
+            overridden: None,
+        };
+
+        let pipeline = AttributePipeline::<Armor>::default();
+        // ((100 + 10) * 1.5 * 2) - 5
+        assert_eq!(
+            pipeline.evaluate(Amount::from_num(100), &values),
+            Amount::from_num(325)
+        );
+
+        let base_only = AttributePipeline::<Armor>::new(vec![
+            ModifierStage::Percent,
+            ModifierStage::Add,
+            ModifierStage::Mult,
+            ModifierStage::Sub,
+        ]);
+        // ((100 * 1.5) + 10) * 2 - 5
+        assert_eq!(
+            base_only.evaluate(Amount::from_num(100), &values),
+            Amount::from_num(315)
+        );
+
+        let overridden = ModifierValues {
+            overridden: Some(Amount::from_num(1)),
+            ..values
+        };
+        assert_eq!(
+            pipeline.evaluate(Amount::from_num(100), &overridden),
+            Amount::from_num(1)
+        );
+
+        let stacked = ModifierValues {
+            mult: Amount::MAX,
+            ..values
+        };
+        assert_eq!(
+            pipeline.evaluate(Amount::from_num(100), &stacked),
+            Amount::MAX - Amount::from_num(5)
+        );
+    }
+}
@@ -0,0 +1,378 @@
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use std::any::type_name;
+use std::marker::PhantomData;
+
+use super::attribute::{
+    basic_modifiers, clamp_max, clamp_min, short_type_name, Add, Attribute, AttributeType, Base,
+    Max, Min, Mult, Override, Percent, Sub,
+};
+use super::event::AttributeChanged;
+use super::max_policy::{apply_max_policy, MaxChangePolicy, MaxPolicy};
+use super::modifier::{aggregate_modifiers, insert_modifier_totals};
+use super::pipeline::AttributePipeline;
+use super::regen::{
+    delay_regen, insert_regen_state, regen, update_regen_clock, Regen, RegenClock, RegenDelay,
+    RegenMode, RegenPercentMax, RegenPercentMissing, RegenPolicy,
+};
+use super::registry::AttributeRegistry;
+use super::snapshot::{snapshot_attributes, Snapshot};
+use super::threshold::{check_thresholds, ThresholdCrossed};
+
+/// Labels for the systems making up the pipeline of a single attribute.
+///
+/// Each label carries the type name of the attribute, so `Attribute<Health>` and
+/// `Attribute<Max<Health>>` get distinct labels without any hand-written strings.
+#[derive(SystemLabel, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttributeLabel {
+    /// Systems computing the terms of a derived `Base<A>`, see `DerivedAttributePlugin`.
+    DeriveTerms(&'static str),
+    /// Sums the derived terms into `Base<A>`.
+    Derive(&'static str),
+    /// `aggregate_modifiers::<A>`
+    Aggregate(&'static str),
+    /// `basic_modifiers::<A>`
+    Modifiers(&'static str),
+    /// `delay_regen::<A>` and `regen::<A>`
+    Regen(&'static str),
+    /// `delay_regen::<A>`
+    RegenDelay(&'static str),
+    /// `apply_max_policy::<A>`
+    MaxPolicy(&'static str),
+    /// `clamp_max::<A>` and `clamp_min::<A>`
+    Clamp(&'static str),
+    /// `check_thresholds::<A>`
+    Thresholds(&'static str),
+    /// `snapshot_attributes::<A>`
+    Snapshot(&'static str),
+}
+
+impl AttributeLabel {
+    pub fn derive_terms<A>() -> Self {
+        Self::DeriveTerms(type_name::<A>())
+    }
+
+    pub fn derive<A>() -> Self {
+        Self::Derive(type_name::<A>())
+    }
+
+    pub fn aggregate<A>() -> Self {
+        Self::Aggregate(type_name::<A>())
+    }
+
+    pub fn modifiers<A>() -> Self {
+        Self::Modifiers(type_name::<A>())
+    }
+
+    pub fn regen<A>() -> Self {
+        Self::Regen(type_name::<A>())
+    }
+
+    pub fn regen_delay<A>() -> Self {
+        Self::RegenDelay(type_name::<A>())
+    }
+
+    pub fn max_policy<A>() -> Self {
+        Self::MaxPolicy(type_name::<A>())
+    }
+
+    pub fn clamp<A>() -> Self {
+        Self::Clamp(type_name::<A>())
+    }
+
+    pub fn thresholds<A>() -> Self {
+        Self::Thresholds(type_name::<A>())
+    }
+
+    pub fn snapshot<A>() -> Self {
+        Self::Snapshot(type_name::<A>())
+    }
+}
+
+/// Attributes which already have their systems added to the app.
+///
+/// Used so `AttributePlugin<Health>` and `AttributePlugin<Max<Health>>` can both be added
+/// without registering `Max<Health>` twice, and so a second plugin for the same attribute
+/// can't silently replace the pipeline or max policy of the first.
+#[derive(Debug, Default)]
+pub struct RegisteredAttributes {
+    systems: HashSet<&'static str>,
+    configured: HashSet<(&'static str, &'static str)>,
+}
+
+impl RegisteredAttributes {
+    pub fn contains<A>(&self) -> bool {
+        self.systems.contains(type_name::<A>())
+    }
+
+    fn register<A>(&mut self) -> bool {
+        self.systems.insert(type_name::<A>())
+    }
+
+    /// Marks the `setting` of `A` as configured, panics if another plugin already did.
+    fn configure<A>(&mut self, setting: &'static str) {
+        if !self.configured.insert((type_name::<A>(), setting)) {
+            panic!(
+                "AttributePlugin<{}> was added twice with a custom {}",
+                type_name::<A>(),
+                setting
+            );
+        }
+    }
+}
+
+/// Registers the whole attribute pipeline for `A`, `Max<A>` and `Min<A>`.
+///
+/// Per frame the pipeline for an attribute sums its `Modifier`s, runs `basic_modifiers`, then
+/// `regen` (advanced by the `RegenClock`), then the clamps and finally checks its `Thresholds`.
+/// The bounds are fully resolved before `A` is clamped against them.
+///
+/// Deeper bounds are added by nesting the plugin, e.g. `AttributePlugin<Max<Health>>` also
+/// registers `Max<Max<Health>>`.
+///
+/// The bounds can't register their own bounds in turn, `Max<Max<Max<..>>>` would never end,
+/// so `Max<A>` and `Min<A>` only get the defaults for their pipeline.
+///
+/// All three are added to the `AttributeRegistry`, under the short type name of `A` unless
+/// another name is given with `with_name`. The bounds are named `Max<name>` and `Min<name>`.
+///
+/// The plugin can be added for the same `A` any number of times, e.g. by `HealthPlugin` and a
+/// `StatSheet`, the systems are only added once. A custom pipeline, max policy or regen mode
+/// applies no matter the order of the plugins, giving `A` two custom ones of the same kind
+/// panics.
+pub struct AttributePlugin<A> {
+    name: Option<String>,
+    pipeline: Option<AttributePipeline<A>>,
+    max_policy: Option<MaxChangePolicy>,
+    regen_mode: Option<RegenMode>,
+}
+
+impl<A> Default for AttributePlugin<A> {
+    fn default() -> Self {
+        Self {
+            name: None,
+            pipeline: None,
+            max_policy: None,
+            regen_mode: None,
+        }
+    }
+}
+
+impl<A> AttributePlugin<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `A` under `name` in the `AttributeRegistry`.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Use a custom modifier pipeline for `A`, see `AttributePipeline`.
+    pub fn with_pipeline(mut self, pipeline: AttributePipeline<A>) -> Self {
+        self.pipeline = Some(pipeline);
+        self
+    }
+
+    /// What happens to the current value of `A` when `Max<A>` changes, see `MaxChangePolicy`.
+    pub fn with_max_policy(mut self, policy: MaxChangePolicy) -> Self {
+        self.max_policy = Some(policy);
+        self
+    }
+
+    /// Whether `A` keeps regenerating at or below zero, see `RegenMode`.
+    pub fn with_regen_mode(mut self, mode: RegenMode) -> Self {
+        self.regen_mode = Some(mode);
+        self
+    }
+}
+
+impl<A> Plugin for AttributePlugin<A>
+where
+    A: AttributeType,
+{
+    fn build(&self, app: &mut App) {
+        if !app.world.contains_resource::<RegisteredAttributes>() {
+            app.add_system_to_stage(CoreStage::PreUpdate, update_regen_clock);
+        }
+
+        app.init_resource::<RegisteredAttributes>()
+            .init_resource::<RegenClock>()
+            .init_resource::<AttributePipeline<A>>()
+            .init_resource::<AttributePipeline<Max<A>>>()
+            .init_resource::<AttributePipeline<Min<A>>>()
+            .init_resource::<AttributeRegistry>()
+            .init_resource::<MaxPolicy<A>>()
+            .init_resource::<RegenPolicy<A>>();
+
+        {
+            let mut registered = app
+                .world
+                .get_resource_mut::<RegisteredAttributes>()
+                .expect("RegisteredAttributes was just initialized");
+            if self.pipeline.is_some() {
+                registered.configure::<A>("pipeline");
+            }
+            if self.max_policy.is_some() {
+                registered.configure::<A>("max policy");
+            }
+            if self.regen_mode.is_some() {
+                registered.configure::<A>("regen mode");
+            }
+        }
+        if let Some(pipeline) = &self.pipeline {
+            app.insert_resource(pipeline.clone());
+        }
+        if let Some(policy) = self.max_policy {
+            app.insert_resource(MaxPolicy::<A>::new(policy));
+        }
+        if let Some(mode) = self.regen_mode {
+            app.insert_resource(RegenPolicy::<A>::new(mode));
+        }
+
+        let name = self.name.clone().unwrap_or_else(short_type_name::<A>);
+        let mut registry = app
+            .world
+            .get_resource_mut::<AttributeRegistry>()
+            .expect("AttributeRegistry was just initialized");
+        registry.register_named::<A>(name.clone());
+        registry.register_named::<Max<A>>(format!("Max<{}>", name));
+        registry.register_named::<Min<A>>(format!("Min<{}>", name));
+
+        add_attribute_systems::<Max<A>>(app, |set| set.before(AttributeLabel::clamp::<A>()));
+        add_attribute_systems::<Min<A>>(app, |set| set.before(AttributeLabel::clamp::<A>()));
+        let newly_registered = add_attribute_systems::<A>(app, |set| {
+            set.after(AttributeLabel::clamp::<Max<A>>())
+                .after(AttributeLabel::clamp::<Min<A>>())
+        });
+
+        if newly_registered {
+            app.add_system(
+                apply_max_policy::<A>
+                    .label(AttributeLabel::max_policy::<A>())
+                    .after(AttributeLabel::clamp::<Max<A>>())
+                    .after(AttributeLabel::regen::<A>())
+                    .before(AttributeLabel::clamp::<A>()),
+            );
+        }
+    }
+}
+
+/// Adds the systems of `A`, returns `false` if they were already added.
+fn add_attribute_systems<A>(app: &mut App, order: impl FnOnce(SystemSet) -> SystemSet) -> bool
+where
+    A: AttributeType,
+{
+    let newly_registered = app
+        .world
+        .get_resource_mut::<RegisteredAttributes>()
+        .expect("RegisteredAttributes should be initialized by the AttributePlugin")
+        .register::<A>();
+
+    if !newly_registered {
+        return false;
+    }
+
+    app.add_event::<AttributeChanged<A>>()
+        .add_event::<ThresholdCrossed<A>>()
+        .register_type::<Attribute<A>>()
+        .register_type::<Attribute<Base<A>>>()
+        .register_type::<Attribute<Add<A>>>()
+        .register_type::<Attribute<Percent<A>>>()
+        .register_type::<Attribute<Mult<A>>>()
+        .register_type::<Attribute<Sub<A>>>()
+        .register_type::<Attribute<Override<A>>>()
+        .register_type::<Attribute<Regen<A>>>()
+        .register_type::<Attribute<RegenPercentMax<A>>>()
+        .register_type::<Attribute<RegenPercentMissing<A>>>()
+        .register_type::<Attribute<RegenDelay<A>>>()
+        .register_type::<Attribute<Snapshot<A>>>();
+
+    app.add_system_to_stage(CoreStage::PreUpdate, insert_modifier_totals::<A>)
+        .add_system_to_stage(CoreStage::PreUpdate, insert_regen_state::<A>);
+
+    let set = SystemSet::new()
+        .with_system(aggregate_modifiers::<A>.label(AttributeLabel::aggregate::<A>()))
+        .with_system(
+            basic_modifiers::<A>
+                .label(AttributeLabel::modifiers::<A>())
+                .after(AttributeLabel::aggregate::<A>()),
+        )
+        .with_system(
+            delay_regen::<A>
+                .label(AttributeLabel::regen::<A>())
+                .label(AttributeLabel::regen_delay::<A>())
+                .after(AttributeLabel::modifiers::<A>()),
+        )
+        .with_system(
+            regen::<A>
+                .label(AttributeLabel::regen::<A>())
+                .after(AttributeLabel::regen_delay::<A>())
+                .after(AttributeLabel::modifiers::<A>()),
+        )
+        .with_system(
+            clamp_max::<A>
+                .label(AttributeLabel::clamp::<A>())
+                .after(AttributeLabel::regen::<A>()),
+        )
+        .with_system(
+            clamp_min::<A>
+                .label(AttributeLabel::clamp::<A>())
+                .after(AttributeLabel::regen::<A>()),
+        )
+        .with_system(
+            check_thresholds::<A>
+                .label(AttributeLabel::thresholds::<A>())
+                .after(AttributeLabel::clamp::<A>()),
+        )
+        .with_system(
+            snapshot_attributes::<A>
+                .label(AttributeLabel::snapshot::<A>())
+                .after(AttributeLabel::clamp::<A>()),
+        );
+
+    app.add_system_set(order(set));
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::attribute::Amount;
+    use crate::ability::attribute::soft_cap::SoftCap;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct MovementSpeed;
+
+    fn capped() -> AttributePlugin<MovementSpeed> {
+        let soft_cap = SoftCap::above(Amount::from_num(415), Amount::from_num(0.8));
+        AttributePlugin::new().with_pipeline(AttributePipeline::default().with_soft_cap(soft_cap))
+    }
+
+    #[test]
+    fn custom_pipeline_wins() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<MovementSpeed>::default())
+            .add_plugin(capped())
+            .add_plugin(AttributePlugin::<MovementSpeed>::default());
+
+        let pipeline = app
+            .world
+            .get_resource::<AttributePipeline<MovementSpeed>>()
+            .unwrap();
+        assert_eq!(pipeline.soft_caps.len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "custom pipeline")]
+    fn conflicting_pipelines() {
+        App::new()
+            .add_plugins(MinimalPlugins)
+            .add_plugin(capped())
+            .add_plugin(capped());
+    }
+}
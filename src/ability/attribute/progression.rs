@@ -0,0 +1,357 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use std::marker::PhantomData;
+
+use super::attribute::{Amount, Attribute, AttributeType, Base};
+use super::event::{change_attribute, AttributeChanged, ChangeCause};
+use super::plugin::{AttributeLabel, AttributePlugin};
+
+/// Total experience gained.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct Xp;
+/// Bonus to experience gained, `0.1` is +10%.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct XpGain;
+/// Current level, starting at 1.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct Level;
+
+/// Total experience needed for each level.
+#[derive(Debug, Clone)]
+pub enum LevelCurve {
+    /// Total experience for level 2, 3, ..., the length is the max level minus one.
+    Table(Vec<Amount>),
+    /// Total experience for a level, up to the given max level.
+    Formula(fn(u32) -> Amount, u32),
+}
+
+impl Default for LevelCurve {
+    /// 18 levels, level `n` needs `100 * (n - 1) * (n + 8) / 2` experience in total.
+    fn default() -> Self {
+        LevelCurve::Formula(|level| Amount::from_num(50 * (level - 1) * (level + 8)), 18)
+    }
+}
+
+impl LevelCurve {
+    pub fn max_level(&self) -> u32 {
+        match self {
+            LevelCurve::Table(table) => table.len() as u32 + 1,
+            LevelCurve::Formula(_, max) => *max,
+        }
+    }
+
+    /// Total experience needed to reach `level`, `None` above the max level.
+    pub fn xp_for_level(&self, level: u32) -> Option<Amount> {
+        if level <= 1 {
+            return Some(Amount::ZERO);
+        }
+        if level > self.max_level() {
+            return None;
+        }
+
+        match self {
+            LevelCurve::Table(table) => table.get(level as usize - 2).copied(),
+            LevelCurve::Formula(formula, _) => Some(formula(level)),
+        }
+    }
+
+    /// Level reached with `xp` total experience.
+    pub fn level_for_xp(&self, xp: Amount) -> u32 {
+        let mut level = 1;
+        while let Some(needed) = self.xp_for_level(level + 1) {
+            if xp < needed {
+                break;
+            }
+            level += 1;
+        }
+        level
+    }
+}
+
+/// Gives `amount` experience to `entity`.
+#[derive(Debug, Clone)]
+pub struct GainExperience {
+    pub entity: Entity,
+    pub amount: Amount,
+    /// Whether the `XpGain` of the entity applies.
+    pub scaled: bool,
+}
+
+impl GainExperience {
+    pub fn new(entity: Entity, amount: Amount) -> Self {
+        Self {
+            entity: entity,
+            amount: amount,
+            scaled: true,
+        }
+    }
+
+    /// Experience `XpGain` doesn't apply to, e.g. to spawn an enemy at a given level.
+    pub fn unscaled(entity: Entity, amount: Amount) -> Self {
+        Self {
+            scaled: false,
+            ..Self::new(entity, amount)
+        }
+    }
+}
+
+/// Sent once for every level an entity gains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelUp {
+    pub entity: Entity,
+    /// The new level.
+    pub level: u32,
+}
+
+#[derive(SystemLabel, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GainExperienceLabel;
+
+pub fn gain_experience(
+    curve: Res<LevelCurve>,
+    mut gains: EventReader<GainExperience>,
+    mut level_ups: EventWriter<LevelUp>,
+    mut xp_changes: EventWriter<AttributeChanged<Xp>>,
+    mut level_changes: EventWriter<AttributeChanged<Level>>,
+    mut query: Query<(
+        &mut Attribute<Xp>,
+        &mut Attribute<Level>,
+        Option<&Attribute<XpGain>>,
+    )>,
+) {
+    for gain in gains.iter() {
+        let (mut xp, mut level, xp_gain) = match query.get_mut(gain.entity) {
+            Ok(components) => components,
+            Err(_) => continue,
+        };
+
+        let mut amount = gain.amount;
+        if let (true, Some(xp_gain)) = (gain.scaled, xp_gain) {
+            amount = amount * (Amount::ONE + *xp_gain.amount());
+        }
+        let total = *xp.amount() + amount;
+        change_attribute(
+            &mut xp_changes,
+            gain.entity,
+            &mut xp,
+            total,
+            ChangeCause::Experience,
+        );
+
+        let current = level.amount().to_i64().max(1) as u32;
+        let reached = curve.level_for_xp(total);
+        for new_level in current + 1..=reached {
+            level_ups.send(LevelUp {
+                entity: gain.entity,
+                level: new_level,
+            });
+        }
+        if reached > current {
+            change_attribute(
+                &mut level_changes,
+                gain.entity,
+                &mut level,
+                Amount::from_num(reached),
+                ChangeCause::Experience,
+            );
+        }
+    }
+}
+
+/// Increase of `Attribute<Base<A>>` per level.
+#[derive(Component, Debug, Clone)]
+pub struct Growth<A> {
+    /// Increase when reaching level 2, 3, ..., the last entry repeats for higher levels.
+    pub per_level: Vec<Amount>,
+    phantom: PhantomData<A>,
+}
+
+impl<A> Growth<A> {
+    /// The same increase every level.
+    pub fn linear(per_level: Amount) -> Self {
+        Self::table(vec![per_level])
+    }
+
+    pub fn table(per_level: Vec<Amount>) -> Self {
+        Self {
+            per_level: per_level,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Increase when reaching `level`.
+    pub fn increase(&self, level: u32) -> Amount {
+        if level <= 1 {
+            return Amount::ZERO;
+        }
+
+        self.per_level
+            .get(level as usize - 2)
+            .or_else(|| self.per_level.last())
+            .copied()
+            .unwrap_or(Amount::ZERO)
+    }
+}
+
+/// Adds the `Growth<A>` of the new level to `Attribute<Base<A>>` on every `LevelUp`.
+///
+/// Entities without an `Attribute<Base<A>>` don't grow.
+pub fn apply_growth<A>(
+    mut level_ups: EventReader<LevelUp>,
+    mut query: Query<(&Growth<A>, &mut Attribute<Base<A>>)>,
+) where
+    A: 'static + Send + Sync,
+{
+    for level_up in level_ups.iter() {
+        if let Ok((growth, mut base)) = query.get_mut(level_up.entity) {
+            let amount = *base.amount() + growth.increase(level_up.level);
+            base.set_amount(amount);
+        }
+    }
+}
+
+/// Experience and level of an entity, starting at level 1.
+#[derive(Bundle)]
+pub struct ProgressionBundle {
+    pub xp: Attribute<Xp>,
+    pub xp_gain: Attribute<XpGain>,
+    pub level: Attribute<Level>,
+}
+
+impl Default for ProgressionBundle {
+    fn default() -> Self {
+        Self {
+            xp: Attribute::default(),
+            xp_gain: Attribute::default(),
+            level: Attribute::new(Amount::ONE),
+        }
+    }
+}
+
+/// Registers `Xp`, `XpGain` and `Level` and levels entities up along the `LevelCurve`.
+#[derive(Default)]
+pub struct ProgressionPlugin {
+    curve: LevelCurve,
+}
+
+impl ProgressionPlugin {
+    pub fn new(curve: LevelCurve) -> Self {
+        Self { curve: curve }
+    }
+}
+
+impl Plugin for ProgressionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(AttributePlugin::<Xp>::default())
+            .add_plugin(AttributePlugin::<XpGain>::default())
+            .add_plugin(AttributePlugin::<Level>::default())
+            .insert_resource(self.curve.clone())
+            .add_event::<GainExperience>()
+            .add_event::<LevelUp>()
+            .add_system(
+                gain_experience
+                    .label(GainExperienceLabel)
+                    .after(AttributeLabel::clamp::<XpGain>()),
+            );
+    }
+}
+
+/// Grows `Attribute<Base<A>>` by `Growth<A>` on level up, `A` needs its own `AttributePlugin`.
+pub struct GrowthPlugin<A>(PhantomData<A>);
+
+impl<A> Default for GrowthPlugin<A> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<A> Plugin for GrowthPlugin<A>
+where
+    A: AttributeType,
+{
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            apply_growth::<A>
+                .after(GainExperienceLabel)
+                .before(AttributeLabel::modifiers::<A>()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::app::Events;
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct AttackDamage;
+
+    #[test]
+    fn level_up() {
+        let curve = LevelCurve::Table(vec![
+            Amount::from_num(100),
+            Amount::from_num(250),
+            Amount::from_num(450),
+        ]);
+        assert_eq!(curve.level_for_xp(Amount::from_num(249)), 2);
+        assert_eq!(curve.level_for_xp(Amount::from_num(10_000)), 4);
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(ProgressionPlugin::new(curve))
+            .add_plugin(AttributePlugin::<AttackDamage>::default())
+            .add_plugin(GrowthPlugin::<AttackDamage>::default());
+
+        let champion = app
+            .world
+            .spawn()
+            .insert_bundle(ProgressionBundle::default())
+            .insert(Attribute::<XpGain>::new(Amount::from_num(0.25)))
+            .insert(Attribute::<AttackDamage>::new(Amount::from_num(60)))
+            .insert(Attribute::<Base<AttackDamage>>::new(Amount::from_num(60)))
+            .insert(Growth::<AttackDamage>::table(vec![
+                Amount::from_num(3),
+                Amount::from_num(4),
+            ]))
+            .id();
+
+        // 200 * 1.25 = 250 is exactly level 3.
+        app.world
+            .get_resource_mut::<Events<GainExperience>>()
+            .unwrap()
+            .send(GainExperience::new(champion, Amount::from_num(200)));
+        app.update();
+
+        let events = app.world.get_resource::<Events<LevelUp>>().unwrap();
+        let levels = events
+            .get_reader()
+            .iter(events)
+            .map(|level_up| level_up.level)
+            .collect::<Vec<_>>();
+        assert_eq!(levels, vec![2, 3]);
+
+        let events = app
+            .world
+            .get_resource::<Events<AttributeChanged<Level>>>()
+            .unwrap();
+        let changes = events
+            .get_reader()
+            .iter(events)
+            .map(|change| (change.old, change.new, change.cause))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![(Amount::ONE, Amount::from_num(3), ChangeCause::Experience)]
+        );
+
+        let entity = app.world.entity(champion);
+        assert_eq!(
+            *entity.get::<Attribute<Level>>().unwrap().amount(),
+            Amount::from_num(3)
+        );
+        assert_eq!(
+            *entity.get::<Attribute<AttackDamage>>().unwrap().amount(),
+            Amount::from_num(67)
+        );
+    }
+}
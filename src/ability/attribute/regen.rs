@@ -1,56 +1,469 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use std::convert::TryFrom;
 use std::marker::PhantomData;
+use std::time::Duration;
 
-use super::attribute::{Amount, Attribute, Max};
+use super::attribute::{attribute_marker, Amount, Attribute, Max, Min};
+use super::event::{change_attribute, AttributeChanged, ChangeCause};
 
-pub struct Regen<A>(PhantomData<A>);
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
 
-pub fn regen<A>(
-    mut query: Query<(
-        &mut Attribute<A>,
-        &Attribute<Regen<A>>,
-        Option<&Attribute<Max<A>>>,
-    )>,
+attribute_marker!(
+    /// Amount `A` regenerates by per second, negative amounts drain it.
+    Regen,
+    /// Fraction of `Max<A>` regenerated per second, `0.02` is 2%.
+    RegenPercentMax,
+    /// Fraction of the missing value `Max<A> - A` regenerated per second.
+    RegenPercentMissing,
+    /// Seconds regen of `A` pauses for after taking damage.
+    ///
+    /// It is an attribute itself, so effects can lengthen or shorten it with the usual
+    /// modifiers once `AttributePlugin<RegenDelay<A>>` is added. Only `AttributeChanged<A>`
+    /// with `ChangeCause::Damage` start the delay, lowering `Max<A>` or paying a cost doesn't.
+    RegenDelay,
+);
+
+/// Bookkeeping of `regen` for `A`, given to every regenerating entity by `insert_regen_state`.
+///
+/// Whatever doesn't fit into the 4 decimal places of an `Amount` in a single frame is carried
+/// over to the next one, so the total regenerated only depends on the elapsed time and not on
+/// the frame rate. This is kept apart from `Regen<A>` and `RegenDelay<A>` so those stay plain
+/// attributes and the state doesn't end up in scenes.
+#[derive(Component)]
+pub struct RegenState<A> {
+    /// Remainder of previous frames, in billionths of `Amount::EPSILON`.
+    carry: i64,
+    /// Time since `A` last took damage, `None` once the `RegenDelay<A>` is over.
+    since_damage: Option<Duration>,
+    phantom: PhantomData<A>,
+}
+
+impl<A> Default for RegenState<A> {
+    fn default() -> Self {
+        Self {
+            carry: 0,
+            since_damage: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A> std::fmt::Debug for RegenState<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegenState")
+            .field("carry", &self.carry)
+            .field("since_damage", &self.since_damage)
+            .finish()
+    }
+}
+
+impl<A> RegenState<A> {
+    /// Time since `A` last took damage while its `RegenDelay<A>` is running.
+    pub fn since_damage(&self) -> Option<Duration> {
+        self.since_damage
+    }
+}
+
+/// `seconds` as a `Duration`, negative amounts are zero.
+fn seconds(seconds: Amount) -> Duration {
+    Duration::from_nanos((seconds.raw().max(0) as u64).saturating_mul(100_000))
+}
+
+/// `rate` per second over `delta`, returns the amount and the new carry.
+fn integrate(rate: Amount, delta: Duration, carry: i64) -> (Amount, i64) {
+    let total = rate.raw() as i128 * delta.as_nanos() as i128 + carry as i128;
+    let raw = total / NANOS_PER_SECOND;
+    let raw = i64::try_from(raw).unwrap_or(if raw > 0 { i64::MAX } else { i64::MIN });
+    (Amount::from_raw(raw), (total % NANOS_PER_SECOND) as i64)
+}
+
+/// Whether `A` keeps regenerating at or below zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RegenMode {
+    /// Always regenerate, e.g. mana.
+    Always,
+    /// Entities at or below zero don't regenerate, e.g. dead players.
+    UnlessZero,
+}
+
+impl Default for RegenMode {
+    fn default() -> Self {
+        RegenMode::Always
+    }
+}
+
+/// `RegenMode` of the attribute `A`.
+#[derive(Debug)]
+pub struct RegenPolicy<A> {
+    pub mode: RegenMode,
+    phantom: PhantomData<A>,
+}
+
+impl<A> RegenPolicy<A> {
+    pub fn new(mode: RegenMode) -> Self {
+        Self {
+            mode: mode,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A> Default for RegenPolicy<A> {
+    fn default() -> Self {
+        Self::new(RegenMode::default())
+    }
+}
+
+/// How far `regen` advances every frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegenStep {
+    /// The frame time from `Time`.
+    Time,
+    /// The same amount of time every frame, for deterministic simulations and tests.
+    Fixed(Duration),
+}
+
+/// Time passed since the last frame as seen by the regen systems.
+#[derive(Debug, Clone)]
+pub struct RegenClock {
+    pub step: RegenStep,
+    delta: Duration,
+}
+
+impl Default for RegenClock {
+    fn default() -> Self {
+        Self::new(RegenStep::Time)
+    }
+}
+
+impl RegenClock {
+    pub fn new(step: RegenStep) -> Self {
+        Self {
+            step: step,
+            delta: Duration::ZERO,
+        }
+    }
+
+    pub fn fixed(step: Duration) -> Self {
+        Self::new(RegenStep::Fixed(step))
+    }
+
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+}
+
+pub fn update_regen_clock(time: Res<Time>, mut clock: ResMut<RegenClock>) {
+    clock.delta = match clock.step {
+        RegenStep::Time => time.delta(),
+        RegenStep::Fixed(step) => step,
+    };
+}
+
+type Regenerating<A> = Or<(
+    With<Attribute<Regen<A>>>,
+    With<Attribute<RegenPercentMax<A>>>,
+    With<Attribute<RegenPercentMissing<A>>>,
+)>;
+
+/// Gives entities which regenerate `A` their `RegenState<A>`.
+///
+/// Runs in `CoreStage::PreUpdate`, so the state already exists when `regen` runs.
+pub fn insert_regen_state<A>(
+    mut commands: Commands,
+    query: Query<Entity, (Regenerating<A>, Without<RegenState<A>>)>,
 ) where
     A: 'static + Send + Sync,
 {
-    for (mut attribute, regen, max) in query.iter_mut() {
-        let mut result = attribute.amount() + regen.amount();
-        if let Some(max) = max {
-            if result > *max.amount() {
-                result = *max.amount();
-            }
-        }
+    for entity in query.iter() {
+        commands.entity(entity).insert(RegenState::<A>::default());
+    }
+}
 
-        if *attribute.amount() != result {
-            attribute.set_amount(result);
+/// Starts the `RegenDelay<A>` of entities which took damage.
+pub fn delay_regen<A>(
+    mut events: EventReader<AttributeChanged<A>>,
+    mut query: Query<&mut RegenState<A>, With<Attribute<RegenDelay<A>>>>,
+) where
+    A: 'static + Send + Sync,
+{
+    for event in events.iter() {
+        if !matches!(event.cause, ChangeCause::Damage(_)) || event.delta >= Amount::ZERO {
+            continue;
+        }
+        if let Ok(mut state) = query.get_mut(event.entity) {
+            state.since_damage = Some(Duration::ZERO);
         }
     }
 }
 
-pub fn regen_unless_zero<A>(
-    mut query: Query<(
-        &mut Attribute<A>,
-        &Attribute<Regen<A>>,
-        Option<&Attribute<Max<A>>>,
-    )>,
+type RegenQuery<'w, 's, A> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Attribute<A>,
+        &'static mut RegenState<A>,
+        Option<&'static Attribute<Regen<A>>>,
+        Option<&'static Attribute<RegenPercentMax<A>>>,
+        Option<&'static Attribute<RegenPercentMissing<A>>>,
+        Option<&'static Attribute<Max<A>>>,
+        Option<&'static Attribute<Min<A>>>,
+        Option<&'static Attribute<RegenDelay<A>>>,
+    ),
+    Regenerating<A>,
+>;
+
+fn regenerate<A>(
+    delta: Duration,
+    events: &mut EventWriter<AttributeChanged<A>>,
+    query: &mut RegenQuery<A>,
+    unless_zero: bool,
 ) where
     A: 'static + Send + Sync,
 {
-    for (mut attribute, regen, max) in query.iter_mut() {
-        if *attribute.amount() <= Amount::ZERO {
+    for (entity, mut attribute, mut state, regen, percent_max, percent_missing, max, min, delay) in
+        query.iter_mut()
+    {
+        let current = *attribute.amount();
+        if unless_zero && current <= Amount::ZERO {
             continue;
         }
 
-        let mut result = attribute.amount() + regen.amount();
+        if let Some(since) = state.since_damage {
+            let delay = delay
+                .map(|delay| seconds(*delay.amount()))
+                .unwrap_or(Duration::ZERO);
+            if since < delay {
+                state.since_damage = Some(since.saturating_add(delta));
+                continue;
+            }
+            state.since_damage = None;
+        }
+
+        let max = max.map(|max| *max.amount());
+        let min = min.map(|min| *min.amount());
+
+        let mut rate = regen.map(|regen| *regen.amount()).unwrap_or(Amount::ZERO);
         if let Some(max) = max {
-            if result > *max.amount() {
-                result = *max.amount();
+            if let Some(percent) = percent_max {
+                rate += max * *percent.amount();
+            }
+            if let Some(percent) = percent_missing {
+                rate += (max - current).max(Amount::ZERO) * *percent.amount();
+            }
+        }
+
+        let (amount, mut carry) = integrate(rate, delta, state.carry);
+        let mut result = current + amount;
+        // Don't bank regen while full, or drain while empty.
+        match max {
+            Some(max) if rate > Amount::ZERO && result >= max => {
+                result = max;
+                carry = 0;
+            }
+            _ => {}
+        }
+        match min {
+            Some(min) if rate < Amount::ZERO && result <= min => {
+                result = min;
+                carry = 0;
             }
+            _ => {}
+        }
+
+        if state.carry != carry {
+            state.carry = carry;
+        }
+        change_attribute(events, entity, &mut attribute, result, ChangeCause::Regen);
+    }
+}
+
+/// Regenerates `A` by its `Regen<A>`, `RegenPercentMax<A>` and `RegenPercentMissing<A>`.
+///
+/// Positive regen stops at `Max<A>`, negative regen (drain) at `Min<A>`. Entities at or below
+/// zero are skipped if the `RegenPolicy<A>` says so.
+pub fn regen<A>(
+    clock: Res<RegenClock>,
+    policy: Option<Res<RegenPolicy<A>>>,
+    mut events: EventWriter<AttributeChanged<A>>,
+    mut query: RegenQuery<A>,
+) where
+    A: 'static + Send + Sync,
+{
+    let unless_zero = policy
+        .map(|policy| policy.mode == RegenMode::UnlessZero)
+        .unwrap_or(false);
+    regenerate(clock.delta(), &mut events, &mut query, unless_zero);
+}
+
+/// `regen`, except entities at or below zero never regenerate whatever the `RegenPolicy<A>`.
+pub fn regen_unless_zero<A>(
+    clock: Res<RegenClock>,
+    mut events: EventWriter<AttributeChanged<A>>,
+    mut query: RegenQuery<A>,
+) where
+    A: 'static + Send + Sync,
+{
+    regenerate(clock.delta(), &mut events, &mut query, true);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::plugin::AttributePlugin;
+    use bevy::app::Events;
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct Energy;
+
+    fn regenerate_for(step: Duration, frames: u32) -> Amount {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<Energy>::default())
+            .insert_resource(RegenClock::fixed(step));
+
+        let player = app
+            .world
+            .spawn()
+            .insert(Attribute::<Energy>::new(Amount::ZERO))
+            .insert(Attribute::<Regen<Energy>>::new(Amount::from_num(0.0007)))
+            .id();
+
+        for _ in 0..frames {
+            app.update();
         }
 
-        if *attribute.amount() != result {
-            attribute.set_amount(result);
+        *app.world.get::<Attribute<Energy>>(player).unwrap().amount()
+    }
+
+    #[test]
+    fn frame_rate_independent() {
+        // 0.0007 per second is less than `Amount::EPSILON` per frame at 60fps.
+        let expected = Amount::from_num(0.0021);
+        assert_eq!(regenerate_for(Duration::from_secs(1), 3), expected);
+        assert_eq!(regenerate_for(Duration::from_millis(750), 4), expected);
+        assert_eq!(regenerate_for(Duration::from_millis(100), 30), expected);
+        assert_eq!(
+            regenerate_for(Duration::from_millis(16), 1500),
+            Amount::from_num(0.0168)
+        );
+    }
+
+    #[test]
+    fn regen_delay() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<Energy>::default())
+            .insert_resource(RegenClock::fixed(Duration::from_secs(1)));
+
+        let player = app
+            .world
+            .spawn()
+            .insert(Attribute::<Energy>::new(Amount::from_num(5)))
+            .insert(Attribute::<Regen<Energy>>::new(Amount::ONE))
+            .insert(Attribute::<RegenDelay<Energy>>::new(Amount::from_num(3)))
+            .id();
+        let energy = |app: &App| *app.world.get::<Attribute<Energy>>(player).unwrap().amount();
+
+        app.update();
+        assert_eq!(energy(&app), Amount::from_num(6));
+
+        // Take a hit.
+        app.world
+            .get_mut::<Attribute<Energy>>(player)
+            .unwrap()
+            .set_amount(Amount::from_num(2));
+        app.world
+            .get_resource_mut::<Events<AttributeChanged<Energy>>>()
+            .unwrap()
+            .send(AttributeChanged::new(
+                player,
+                Amount::from_num(6),
+                Amount::from_num(2),
+                ChangeCause::Damage(None),
+            ));
+        for _ in 0..3 {
+            app.update();
+            assert_eq!(energy(&app), Amount::from_num(2));
         }
+
+        app.update();
+        assert_eq!(energy(&app), Amount::from_num(3));
+
+        // Cast a spell, only damage pauses regen.
+        app.world
+            .get_mut::<Attribute<Energy>>(player)
+            .unwrap()
+            .set_amount(Amount::ONE);
+        app.update();
+        assert_eq!(energy(&app), Amount::from_num(2));
+    }
+
+    #[test]
+    fn regen_modes() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<Energy>::default())
+            .insert_resource(RegenClock::fixed(Duration::from_secs(1)));
+
+        let player = app
+            .world
+            .spawn()
+            .insert(Attribute::<Energy>::new(Amount::from_num(50)))
+            .insert(Attribute::<Max<Energy>>::new(Amount::from_num(100)))
+            .insert(Attribute::<Regen<Energy>>::new(Amount::ONE))
+            .insert(Attribute::<RegenPercentMax<Energy>>::new(Amount::from_num(
+                0.02,
+            )))
+            .insert(Attribute::<RegenPercentMissing<Energy>>::new(
+                Amount::from_num(0.1),
+            ))
+            .id();
+        let drained = app
+            .world
+            .spawn()
+            .insert(Attribute::<Energy>::new(Amount::from_num(2)))
+            .insert(Attribute::<Min<Energy>>::new(Amount::ZERO))
+            .insert(Attribute::<Regen<Energy>>::new(Amount::from_num(-3)))
+            .id();
+
+        app.update();
+        // 1 + 2% of 100 + 10% of 50
+        assert_eq!(
+            *app.world.get::<Attribute<Energy>>(player).unwrap().amount(),
+            Amount::from_num(58)
+        );
+        assert_eq!(
+            *app.world
+                .get::<Attribute<Energy>>(drained)
+                .unwrap()
+                .amount(),
+            Amount::ZERO
+        );
+    }
+
+    #[test]
+    fn regen_stops_at_max() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<Energy>::default())
+            .insert_resource(RegenClock::fixed(Duration::from_millis(500)));
+
+        let player = app
+            .world
+            .spawn()
+            .insert(Attribute::<Energy>::new(Amount::from_num(9)))
+            .insert(Attribute::<Max<Energy>>::new(Amount::from_num(10)))
+            .insert(Attribute::<Regen<Energy>>::new(Amount::from_num(3)))
+            .id();
+
+        app.update();
+        assert_eq!(
+            *app.world.get::<Attribute<Energy>>(player).unwrap().amount(),
+            Amount::from_num(10)
+        );
     }
 }
@@ -0,0 +1,273 @@
+use bevy::ecs::world::{EntityMut, EntityRef};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+use std::any::{type_name, TypeId};
+use std::fmt;
+use std::hash::Hasher;
+
+use super::attribute::{short_type_name, Amount, Attribute};
+
+/// Stable numeric id of a registered attribute.
+///
+/// The id is a hash of the attribute's registered name, so it does not depend on the order
+/// attributes are registered in and can be stored in save files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct AttributeId(pub u64);
+
+impl AttributeId {
+    pub fn from_name(name: &str) -> Self {
+        let mut hasher = fxhash::FxHasher64::default();
+        hasher.write(name.as_bytes());
+        Self(hasher.finish())
+    }
+}
+
+impl fmt::Display for AttributeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Reference to an attribute in data files, either by name or by numeric id.
+///
+/// In RON `"attribute": "Health"` and `"attribute": 1234` both deserialize into this.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AttributeKey {
+    Id(u64),
+    Name(String),
+}
+
+impl AttributeKey {
+    pub fn resolve(&self, registry: &AttributeRegistry) -> Option<AttributeId> {
+        match self {
+            AttributeKey::Id(id) => registry.info(AttributeId(*id)).map(|info| info.id),
+            AttributeKey::Name(name) => registry.resolve(name),
+        }
+    }
+}
+
+/// Type erased accessors for a single registered `Attribute<A>`.
+#[derive(Clone)]
+pub struct AttributeInfo {
+    pub name: String,
+    pub id: AttributeId,
+    pub type_id: TypeId,
+    pub type_name: &'static str,
+    get: fn(&EntityRef) -> Option<Amount>,
+    set: fn(&mut EntityMut, Amount) -> bool,
+}
+
+impl fmt::Debug for AttributeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AttributeInfo")
+            .field("name", &self.name)
+            .field("id", &self.id)
+            .field("type_name", &self.type_name)
+            .finish()
+    }
+}
+
+fn get_attribute<A>(entity: &EntityRef) -> Option<Amount>
+where
+    A: 'static + Send + Sync,
+{
+    entity
+        .get::<Attribute<A>>()
+        .map(|attribute| *attribute.amount())
+}
+
+fn set_attribute<A>(entity: &mut EntityMut, amount: Amount) -> bool
+where
+    A: 'static + Send + Sync,
+{
+    match entity.get_mut::<Attribute<A>>() {
+        Some(mut attribute) => {
+            if *attribute.amount() != amount {
+                attribute.set_amount(amount);
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+/// Every attribute registered with an `AttributePlugin`, by name, id and type.
+///
+/// This is what lets data driven abilities, debug consoles and save files refer to attributes
+/// without knowing their type. Values written through the registry trigger change detection
+/// like any other write, but do not send an `AttributeChanged<A>`.
+#[derive(Debug, Clone, Default)]
+pub struct AttributeRegistry {
+    infos: Vec<AttributeInfo>,
+    by_name: HashMap<String, usize>,
+    by_id: HashMap<AttributeId, usize>,
+    by_type: HashMap<TypeId, usize>,
+}
+
+impl AttributeRegistry {
+    /// Registers `A` under its short type name, e.g. `Max<Health>`.
+    pub fn register<A>(&mut self) -> AttributeId
+    where
+        A: 'static + Send + Sync,
+    {
+        self.register_named::<A>(short_type_name::<A>())
+    }
+
+    /// Registers `A` under `name`, registering an already registered type again keeps the
+    /// original name.
+    ///
+    /// Panics if `name` (or its id) is already used by a different attribute.
+    pub fn register_named<A>(&mut self, name: impl Into<String>) -> AttributeId
+    where
+        A: 'static + Send + Sync,
+    {
+        if let Some(id) = self.id::<A>() {
+            return id;
+        }
+
+        let name = name.into();
+        let id = AttributeId::from_name(&name);
+        if let Some(existing) = self.by_id.get(&id).map(|index| &self.infos[*index]) {
+            panic!(
+                "attribute `{}` ({}) can't be registered as `{}`, id {} is already used by `{}`",
+                type_name::<A>(),
+                short_type_name::<A>(),
+                name,
+                id,
+                existing.type_name,
+            );
+        }
+
+        let index = self.infos.len();
+        self.infos.push(AttributeInfo {
+            name: name.clone(),
+            id: id,
+            type_id: TypeId::of::<A>(),
+            type_name: type_name::<A>(),
+            get: get_attribute::<A>,
+            set: set_attribute::<A>,
+        });
+        self.by_name.insert(name, index);
+        self.by_id.insert(id, index);
+        self.by_type.insert(TypeId::of::<A>(), index);
+        id
+    }
+
+    pub fn id<A>(&self) -> Option<AttributeId>
+    where
+        A: 'static,
+    {
+        self.by_type
+            .get(&TypeId::of::<A>())
+            .map(|index| self.infos[*index].id)
+    }
+
+    /// Id of the attribute registered under `name`.
+    pub fn resolve(&self, name: &str) -> Option<AttributeId> {
+        self.by_name.get(name).map(|index| self.infos[*index].id)
+    }
+
+    pub fn info(&self, id: AttributeId) -> Option<&AttributeInfo> {
+        self.by_id.get(&id).map(|index| &self.infos[*index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AttributeInfo> {
+        self.infos.iter()
+    }
+
+    /// Current value of the attribute on `entity`, `None` if the attribute isn't registered
+    /// or the entity doesn't have it.
+    pub fn get(&self, entity: &EntityRef, id: AttributeId) -> Option<Amount> {
+        self.info(id).and_then(|info| (info.get)(entity))
+    }
+
+    /// Sets the attribute on `entity`, returns `false` if the attribute isn't registered or
+    /// the entity doesn't have it.
+    pub fn set(&self, entity: &mut EntityMut, id: AttributeId, amount: Amount) -> bool {
+        match self.info(id) {
+            Some(info) => (info.set)(entity, amount),
+            None => false,
+        }
+    }
+
+    /// Replaces the attribute on `entity` with the result of `modify`.
+    pub fn modify(
+        &self,
+        entity: &mut EntityMut,
+        id: AttributeId,
+        modify: impl FnOnce(Amount) -> Amount,
+    ) -> bool {
+        let current = match self
+            .info(id)
+            .and_then(|info| (info.get)(&entity_ref(entity)))
+        {
+            Some(current) => current,
+            None => return false,
+        };
+
+        self.set(entity, id, modify(current))
+    }
+}
+
+fn entity_ref<'w>(entity: &'w EntityMut) -> EntityRef<'w> {
+    entity.world().entity(entity.id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::attribute::Max;
+    use crate::ability::attribute::plugin::AttributePlugin;
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct Health;
+
+    #[derive(Debug, Deserialize)]
+    struct DamageDefinition {
+        attribute: AttributeKey,
+    }
+
+    #[test]
+    fn registry_access() {
+        let mut app = App::new();
+        app.add_plugin(AttributePlugin::<Health>::default());
+
+        let player = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(10)))
+            .insert(Attribute::<Max<Health>>::new(Amount::from_num(20)))
+            .id();
+
+        let definition: DamageDefinition = ron::from_str(r#"(attribute: "Health")"#).unwrap();
+
+        app.world
+            .resource_scope(|world, registry: Mut<AttributeRegistry>| {
+                let health = definition.attribute.resolve(&registry).unwrap();
+                assert_eq!(registry.id::<Health>(), Some(health));
+                assert_eq!(
+                    registry.resolve("Max<Health>"),
+                    registry.id::<Max<Health>>()
+                );
+                assert_eq!(AttributeKey::Id(health.0).resolve(&registry), Some(health));
+
+                assert_eq!(
+                    registry.get(&world.entity(player), health),
+                    Some(Amount::from_num(10))
+                );
+                assert!(
+                    registry.modify(&mut world.entity_mut(player), health, |health| {
+                        health - Amount::from_num(3)
+                    })
+                );
+            });
+
+        assert_eq!(
+            *app.world.get::<Attribute<Health>>(player).unwrap().amount(),
+            Amount::from_num(7)
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use std::marker::PhantomData;
+
+use super::attribute::{attribute_marker, Amount, Attribute};
+use crate::ability::caster::Caster;
+
+attribute_marker!(
+    /// Value of `A` copied from the `Caster` when the entity was spawned.
+    Snapshot,
+);
+
+/// Copies the `Attribute<A>` of the `Caster` onto this entity as `Attribute<Snapshot<A>>`
+/// when it is spawned.
+///
+/// Useful for projectiles and effects whose damage shouldn't change if the caster swaps
+/// items or dies while they are in flight.
+#[derive(Component, Debug, Clone)]
+pub struct SnapshotAttribute<A>(PhantomData<A>);
+
+impl<A> Default for SnapshotAttribute<A> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<A> SnapshotAttribute<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+pub fn snapshot_attributes<A>(
+    mut commands: Commands,
+    requests: Query<(Entity, &Caster), Added<SnapshotAttribute<A>>>,
+    casters: Query<&Attribute<A>>,
+) where
+    A: 'static + Send + Sync,
+{
+    for (entity, caster) in requests.iter() {
+        if let Ok(attribute) = casters.get(caster.entity()) {
+            commands
+                .entity(entity)
+                .insert(Attribute::<Snapshot<A>>::new(*attribute.amount()));
+        }
+    }
+}
+
+/// Where a formula reads a caster's attribute from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ValueSource {
+    /// The current value on the caster.
+    Live,
+    /// The value the caster had when the ability or effect was spawned.
+    Snapshot,
+}
+
+/// Reads the `Attribute<A>` of the caster of an ability or effect, either live or from its
+/// snapshot.
+#[derive(SystemParam)]
+pub struct CasterAttributes<'w, 's, A>
+where
+    A: 'static + Send + Sync,
+{
+    casters: Query<'w, 's, &'static Caster>,
+    live: Query<'w, 's, &'static Attribute<A>>,
+    snapshots: Query<'w, 's, &'static Attribute<Snapshot<A>>>,
+}
+
+impl<'w, 's, A> CasterAttributes<'w, 's, A>
+where
+    A: 'static + Send + Sync,
+{
+    /// Value of `A` for the caster of `entity`.
+    ///
+    /// `ValueSource::Snapshot` does not fall back to the live value, `None` means no snapshot
+    /// was taken.
+    pub fn get(&self, entity: Entity, source: ValueSource) -> Option<Amount> {
+        match source {
+            ValueSource::Live => {
+                let caster = self.casters.get(entity).ok()?;
+                self.live
+                    .get(caster.entity())
+                    .ok()
+                    .map(|attribute| *attribute.amount())
+            }
+            ValueSource::Snapshot => self
+                .snapshots
+                .get(entity)
+                .ok()
+                .map(|attribute| *attribute.amount()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::plugin::AttributePlugin;
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct AttackDamage;
+
+    #[derive(Default)]
+    struct Read {
+        live: Option<Amount>,
+        snapshot: Option<Amount>,
+    }
+
+    fn read_damage(
+        mut read: ResMut<Read>,
+        abilities: Query<Entity, With<SnapshotAttribute<AttackDamage>>>,
+        damage: CasterAttributes<AttackDamage>,
+    ) {
+        for ability in abilities.iter() {
+            read.live = damage.get(ability, ValueSource::Live);
+            read.snapshot = damage.get(ability, ValueSource::Snapshot);
+        }
+    }
+
+    #[test]
+    fn snapshot_on_spawn() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<AttackDamage>::default())
+            .init_resource::<Read>()
+            .add_system(read_damage);
+
+        let caster = app
+            .world
+            .spawn()
+            .insert(Attribute::<AttackDamage>::new(Amount::from_num(50)))
+            .id();
+
+        let projectile = app
+            .world
+            .spawn()
+            .insert(Caster(caster))
+            .insert(SnapshotAttribute::<AttackDamage>::new())
+            .id();
+
+        app.update();
+        assert_eq!(
+            *app.world
+                .get::<Attribute<Snapshot<AttackDamage>>>(projectile)
+                .unwrap()
+                .amount(),
+            Amount::from_num(50)
+        );
+
+        // Caster swaps items mid-flight.
+        app.world
+            .get_mut::<Attribute<AttackDamage>>(caster)
+            .unwrap()
+            .set_amount(Amount::from_num(80));
+        app.update();
+
+        let read = app.world.get_resource::<Read>().unwrap();
+        assert_eq!(read.live, Some(Amount::from_num(80)));
+        assert_eq!(read.snapshot, Some(Amount::from_num(50)));
+    }
+}
@@ -0,0 +1,155 @@
+use super::attribute::Amount;
+
+/// Curve applied to an attribute after its modifiers and before it is clamped.
+///
+/// Added to an attribute with `AttributePipeline::with_soft_cap`, the stored `Attribute<A>` is
+/// the value after all of its soft caps.
+#[derive(Debug, Clone)]
+pub enum SoftCap {
+    /// Piecewise linear diminishing returns, above each `(threshold, factor)` only `factor` of
+    /// the excess is kept.
+    ///
+    /// Thresholds are in the uncapped value and ascending, see `SoftCap::diminishing`.
+    Diminishing(Vec<(Amount, Amount)>),
+    /// `value / (value + constant)`, e.g. armor to damage reduction with a constant of 100.
+    ///
+    /// Negative values count as zero, and so does anything over a denominator of zero or less.
+    Hyperbolic(Amount),
+    /// Any other curve.
+    Curve(fn(Amount) -> Amount),
+}
+
+impl SoftCap {
+    /// Diminishing returns above the given `(threshold, factor)` pairs, sorted by threshold.
+    ///
+    /// Movement speed above 415 reduced by 20% and above 490 by 50% is
+    /// `SoftCap::diminishing(vec![(415, 0.8), (490, 0.5)])` in amounts.
+    pub fn diminishing(mut breakpoints: Vec<(Amount, Amount)>) -> Self {
+        breakpoints.sort_by_key(|(threshold, _)| *threshold);
+        SoftCap::Diminishing(breakpoints)
+    }
+
+    /// A single threshold above which only `factor` of the excess is kept.
+    pub fn above(threshold: Amount, factor: Amount) -> Self {
+        SoftCap::Diminishing(vec![(threshold, factor)])
+    }
+
+    pub fn apply(&self, value: Amount) -> Amount {
+        match self {
+            SoftCap::Diminishing(breakpoints) => {
+                let first = match breakpoints.first() {
+                    Some((threshold, _)) if value > *threshold => *threshold,
+                    _ => return value,
+                };
+
+                let mut result = first;
+                for (index, (threshold, factor)) in breakpoints.iter().enumerate() {
+                    if value <= *threshold {
+                        break;
+                    }
+
+                    let upper = breakpoints
+                        .get(index + 1)
+                        .map(|(next, _)| value.min(*next))
+                        .unwrap_or(value);
+                    result += (upper - *threshold) * *factor;
+                }
+
+                result
+            }
+            SoftCap::Hyperbolic(constant) => {
+                let value = value.max(Amount::ZERO);
+                let denominator = value + *constant;
+                if denominator > Amount::ZERO {
+                    value / denominator
+                } else {
+                    Amount::ZERO
+                }
+            }
+            SoftCap::Curve(curve) => curve(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::attribute::{Attribute, Base};
+    use crate::ability::attribute::pipeline::AttributePipeline;
+    use crate::ability::attribute::plugin::AttributePlugin;
+    use bevy::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct MovementSpeed;
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct DamageReduction;
+
+    #[test]
+    fn soft_caps() {
+        let movement_speed = SoftCap::diminishing(vec![
+            (Amount::from_num(490), Amount::from_num(0.5)),
+            (Amount::from_num(415), Amount::from_num(0.8)),
+        ]);
+        assert_eq!(
+            movement_speed.apply(Amount::from_num(400)),
+            Amount::from_num(400)
+        );
+        assert_eq!(
+            movement_speed.apply(Amount::from_num(450)),
+            Amount::from_num(443)
+        );
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(
+                AttributePlugin::<MovementSpeed>::new()
+                    .with_pipeline(AttributePipeline::default().with_soft_cap(movement_speed)),
+            )
+            .add_plugin(
+                AttributePlugin::<DamageReduction>::new().with_pipeline(
+                    AttributePipeline::default()
+                        .with_soft_cap(SoftCap::Hyperbolic(Amount::from_num(100))),
+                ),
+            );
+
+        let player = app
+            .world
+            .spawn()
+            .insert(Attribute::<MovementSpeed>::default())
+            .insert(Attribute::<Base<MovementSpeed>>::new(Amount::from_num(500)))
+            .insert(Attribute::<DamageReduction>::default())
+            .insert(Attribute::<Base<DamageReduction>>::new(Amount::from_num(
+                100,
+            )))
+            .id();
+        app.update();
+
+        // 415 + 75 * 0.8 + 10 * 0.5
+        assert_eq!(
+            *app.world
+                .get::<Attribute<MovementSpeed>>(player)
+                .unwrap()
+                .amount(),
+            Amount::from_num(480)
+        );
+        assert_eq!(
+            *app.world
+                .get::<Attribute<DamageReduction>>(player)
+                .unwrap()
+                .amount(),
+            Amount::from_num(0.5)
+        );
+    }
+
+    #[test]
+    fn hyperbolic_bounds() {
+        let hyperbolic = SoftCap::Hyperbolic(Amount::from_num(100));
+        assert_eq!(hyperbolic.apply(Amount::from_num(-100)), Amount::ZERO);
+        assert_eq!(hyperbolic.apply(Amount::from_num(-50)), Amount::ZERO);
+        assert_eq!(
+            SoftCap::Hyperbolic(Amount::ZERO).apply(Amount::ZERO),
+            Amount::ZERO
+        );
+    }
+}
@@ -0,0 +1,264 @@
+//! Stats most MOBAs and ARPGs share and their conventional formulas.
+//!
+//! Only compiled with the `standard-stats` feature. Add `StandardStatsPlugin` to register all of
+//! them, percentages are fractions so `0.25` is 25%.
+//!
+//! `AttackDamage`, `AbilityPower`, `AttackSpeed` and `Resource` are data only, forte has no
+//! attacks or ability costs to wire them into. Neither does it have crowd control or cooldowns,
+//! `cc_duration` and `cooldown` apply `Tenacity` and `CooldownReduction` in the game's own code.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::attribute::{Amount, AttributeType};
+pub use super::attribute::{AttackSpeed, Health, MovementSpeed};
+use super::currency::{CurrencyPlugin, Gain};
+use super::health::HealthPlugin;
+use super::pipeline::AttributePipeline;
+use super::plugin::AttributePlugin;
+pub use super::progression::{Level, Xp, XpGain};
+use super::soft_cap::SoftCap;
+use crate::ability::damage::crit::CritMode;
+use crate::ability::damage::resist::Mitigation;
+pub use crate::ability::damage::resist::{damage_multiplier, effective_resist};
+pub use crate::ability::damage::scale_damage;
+use crate::ability::damage::shield::ShieldingPower;
+use crate::ability::damage::{
+    Amplification, DamagePipelinePlugin, DamageType, Reduction, Reflection,
+};
+use crate::ability::heal::HealingPower;
+
+macro_rules! stats {
+    ($($(#[$meta:meta])* $name:ident),* $(,)?) => {
+        $(
+            $(#[$meta])*
+            #[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+            pub struct $name;
+        )*
+    };
+}
+
+stats!(
+    /// Scales physical damage.
+    AttackDamage,
+    /// Scales magic damage.
+    AbilityPower,
+    /// Mitigates physical damage, see `damage_multiplier`.
+    Armor,
+    /// Mitigates magic damage, see `damage_multiplier`.
+    MagicResist,
+    /// Mana, energy or whatever abilities are paid with.
+    Resource,
+    /// Flat armor ignored by physical damage.
+    PhysicalPenetration,
+    /// Flat magic resist ignored by magic damage.
+    MagicPenetration,
+    /// Fraction of armor ignored by physical damage.
+    PercentPhysicalPenetration,
+    /// Fraction of magic resist ignored by magic damage.
+    PercentMagicPenetration,
+    /// Fraction cooldowns are reduced by, see `cooldown`.
+    CooldownReduction,
+    /// Chance of a critical strike.
+    CritChance,
+    /// Multiplier of critical strikes, `2` doubles the damage.
+    CritDamage,
+    /// Fraction of basic attack damage dealt healed back.
+    Lifesteal,
+    /// Fraction of ability damage dealt healed back, a third for area abilities.
+    SpellVamp,
+    /// Fraction crowd control durations are reduced by, see `cc_duration`.
+    Tenacity,
+    /// Earned and spent through `Transaction<Gold>`.
+    Gold,
+);
+
+/// Bonus to gold earned.
+pub type GoldGain = Gain<Gold>;
+
+/// Fraction of incoming damage ignored, read by the `mitigate` stage of the damage pipeline.
+pub type DamageReduction = Reduction<Health>;
+
+/// Bonus to outgoing damage, read by the `mitigate` stage of the damage pipeline.
+pub type DamageAmplification = Amplification<Health>;
+
+/// Fraction of incoming damage dealt back to the attacker.
+pub type DamageReflect = Reflection<Health>;
+
+/// Bonus to healing done through `HealEvent<Health>`.
+pub type HealPower = HealingPower<Health>;
+
+/// Bonus to shields given through `GiveShield<Health>`.
+pub type ShieldPower = ShieldingPower<Health>;
+
+/// Cooldown after `CooldownReduction`.
+pub fn cooldown(cooldown: Amount, reduction: Amount) -> Amount {
+    cooldown * (Amount::ONE - fraction(reduction))
+}
+
+/// Crowd control duration after `Tenacity`.
+pub fn cc_duration(duration: Amount, tenacity: Amount) -> Amount {
+    duration * (Amount::ONE - fraction(tenacity))
+}
+
+/// Keeps fractions like `CritChance` or `Tenacity` within `0..=1`.
+pub fn fraction(value: Amount) -> Amount {
+    value.max(Amount::ZERO).min(Amount::ONE)
+}
+
+/// Damage pipeline of `A` rolling crits from `CritChance` and `CritDamage`, mitigating
+/// physical damage by `Armor` and magic damage by `MagicResist`, penetrated by the matching
+/// penetration stats of the source, and healing the source by `Lifesteal` and `SpellVamp`.
+///
+/// `DamageAmplification`, `DamageReduction` and `DamageReflect` are read by every damage
+/// pipeline of `Health`. Needs the `HealPipelinePlugin<A>` for the heals.
+pub fn standard_damage<A>() -> DamagePipelinePlugin<A>
+where
+    A: 'static + Send + Sync,
+{
+    DamagePipelinePlugin::default()
+        .with_crits::<CritChance, CritDamage>(CritMode::Random)
+        .with_penetrable_resistance::<Armor, PhysicalPenetration, PercentPhysicalPenetration>(
+            DamageType::Physical,
+            Mitigation::Multiplier,
+        )
+        .with_penetrable_resistance::<MagicResist, MagicPenetration, PercentMagicPenetration>(
+            DamageType::Magic,
+            Mitigation::Multiplier,
+        )
+        .with_vamp::<Lifesteal, SpellVamp>(Amount::ONE / Amount::from_num(3))
+}
+
+fn capped<A: AttributeType>(soft_cap: SoftCap) -> AttributePlugin<A> {
+    AttributePlugin::new().with_pipeline(AttributePipeline::default().with_soft_cap(soft_cap))
+}
+
+/// Registers every standard stat, `Health` through `HealthPlugin`.
+///
+/// Movement speed has diminishing returns above 415 and 490, fractions like `CritChance`,
+/// `CooldownReduction` or `Tenacity` are kept within `0..=1`.
+#[derive(Default)]
+pub struct StandardStatsPlugin;
+
+impl Plugin for StandardStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(HealthPlugin)
+            .add_plugin(AttributePlugin::<Resource>::default())
+            .add_plugin(AttributePlugin::<AttackDamage>::default())
+            .add_plugin(AttributePlugin::<AbilityPower>::default())
+            .add_plugin(AttributePlugin::<Armor>::default())
+            .add_plugin(AttributePlugin::<MagicResist>::default())
+            .add_plugin(AttributePlugin::<PhysicalPenetration>::default())
+            .add_plugin(AttributePlugin::<MagicPenetration>::default())
+            .add_plugin(capped::<PercentPhysicalPenetration>(SoftCap::Curve(
+                fraction,
+            )))
+            .add_plugin(capped::<PercentMagicPenetration>(SoftCap::Curve(fraction)))
+            .add_plugin(capped::<CooldownReduction>(SoftCap::Curve(fraction)))
+            .add_plugin(capped::<CritChance>(SoftCap::Curve(fraction)))
+            .add_plugin(AttributePlugin::<CritDamage>::default())
+            .add_plugin(AttributePlugin::<AttackSpeed>::default())
+            .add_plugin(capped::<MovementSpeed>(SoftCap::diminishing(vec![
+                (Amount::from_num(415), Amount::from_num(0.8)),
+                (Amount::from_num(490), Amount::from_num(0.5)),
+            ])))
+            .add_plugin(AttributePlugin::<Lifesteal>::default())
+            .add_plugin(AttributePlugin::<SpellVamp>::default())
+            .add_plugin(AttributePlugin::<HealPower>::default())
+            .add_plugin(AttributePlugin::<ShieldPower>::default())
+            .add_plugin(capped::<Tenacity>(SoftCap::Curve(fraction)))
+            .add_plugin(capped::<DamageReduction>(SoftCap::Curve(fraction)))
+            .add_plugin(AttributePlugin::<DamageAmplification>::default())
+            .add_plugin(AttributePlugin::<DamageReflect>::default())
+            .add_plugin(AttributePlugin::<Xp>::default())
+            .add_plugin(AttributePlugin::<XpGain>::default())
+            .add_plugin(CurrencyPlugin::<Gold>::default())
+            .add_plugin(AttributePlugin::<GoldGain>::default())
+            .add_plugin(AttributePlugin::<Level>::default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::attribute::{Attribute, Max};
+    use crate::ability::attribute::registry::AttributeRegistry;
+    use crate::ability::damage::{DamageEvent, DamageTag};
+    use crate::ability::heal::HealPipelinePlugin;
+    use bevy::app::Events;
+
+    #[test]
+    fn formulas() {
+        let hundred = Amount::from_num(100);
+        assert_eq!(damage_multiplier(hundred), Amount::from_num(0.5));
+        assert_eq!(damage_multiplier(-hundred), Amount::from_num(1.5));
+
+        // 100 armor, 30% then 10 flat penetration leaves 60 armor.
+        assert_eq!(
+            effective_resist(hundred, Amount::from_num(10), Amount::from_num(0.3)),
+            Amount::from_num(60)
+        );
+        assert_eq!(
+            effective_resist(Amount::from_num(5), Amount::from_num(10), Amount::ZERO),
+            Amount::ZERO
+        );
+        assert_eq!(
+            cc_duration(Amount::from_num(2), Amount::from_num(0.3)),
+            Amount::from_num(1.4)
+        );
+        assert_eq!(fraction(Amount::from_num(1.5)), Amount::ONE);
+    }
+
+    #[test]
+    fn standard_damage_pipeline() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(StandardStatsPlugin)
+            .add_plugin(HealPipelinePlugin::<Health>::default())
+            .add_plugin(standard_damage::<Health>());
+
+        let attacker = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(50)))
+            .insert(Attribute::<Max<Health>>::new(Amount::from_num(100)))
+            .insert(Attribute::<PhysicalPenetration>::new(Amount::from_num(20)))
+            .insert(Attribute::<Lifesteal>::new(Amount::from_num(0.5)))
+            .id();
+        let target = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(1000)))
+            .insert(Attribute::<Armor>::new(Amount::from_num(120)))
+            .id();
+
+        let mut damage = app
+            .world
+            .get_resource_mut::<Events<DamageEvent<Health>>>()
+            .unwrap();
+        // 120 armor with 20 penetration halves the damage, half of which is stolen back.
+        damage.send(
+            DamageEvent::new(Some(attacker), target, Amount::from_num(100))
+                .with_type(DamageType::Physical)
+                .with_tag(DamageTag::BasicAttack),
+        );
+        app.update();
+
+        let health = |entity| *app.world.get::<Attribute<Health>>(entity).unwrap().amount();
+        assert_eq!(health(target), Amount::from_num(950));
+        assert_eq!(health(attacker), Amount::from_num(75));
+    }
+
+    #[test]
+    fn registers_stats() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(StandardStatsPlugin);
+
+        let registry = app.world.get_resource::<AttributeRegistry>().unwrap();
+        assert!(registry.resolve("Armor").is_some());
+        assert!(registry.resolve("Max<Health>").is_some());
+        assert!(registry.resolve("Reduction<Health>").is_some());
+        assert!(registry.resolve("Tenacity").is_some());
+    }
+}
@@ -0,0 +1,58 @@
+//! `#[derive(StatSheet)]` turns a struct listing attributes into a bundle with their starting
+//! values and a plugin registering all of them, see `forte_derive::StatSheet`.
+
+pub use forte_derive::StatSheet;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::attribute::{Amount, Attribute, Base, Health, Max, Min};
+    use crate::ability::attribute::registry::AttributeRegistry;
+    use bevy::prelude::*;
+
+    #[derive(StatSheet)]
+    pub struct Champion {
+        #[stat(max = 580, min = 0, regen = 2.5)]
+        health: Health,
+        #[stat(marker, name = "Armor", base = 30)]
+        armor: Armor,
+    }
+
+    #[test]
+    fn champion_stat_sheet() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins).add_plugin(ChampionPlugin);
+
+        let champion = app
+            .world
+            .spawn()
+            .insert_bundle(ChampionBundle::from(Champion {
+                health: Health,
+                armor: Armor,
+            }))
+            .id();
+        app.update();
+
+        let entity = app.world.entity(champion);
+        assert_eq!(
+            *entity.get::<Attribute<Health>>().unwrap().amount(),
+            Amount::from_num(580)
+        );
+        assert_eq!(
+            *entity.get::<Attribute<Max<Health>>>().unwrap().amount(),
+            Amount::from_num(580)
+        );
+        assert_eq!(
+            *entity.get::<Attribute<Min<Health>>>().unwrap().amount(),
+            Amount::ZERO
+        );
+        assert_eq!(
+            *entity.get::<Attribute<Armor>>().unwrap().amount(),
+            Amount::from_num(30)
+        );
+        assert!(entity.contains::<Attribute<Base<Armor>>>());
+
+        let registry = app.world.get_resource::<AttributeRegistry>().unwrap();
+        assert_eq!(registry.resolve("Armor"), registry.id::<Armor>());
+    }
+}
@@ -0,0 +1,320 @@
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+
+use std::marker::PhantomData;
+
+use super::attribute::{Amount, Attribute, Max};
+
+/// Where a threshold lies.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThresholdValue {
+    Absolute(Amount),
+    /// Fraction of `Max<A>`, `0.25` is 25% of the maximum. Never triggers without a `Max<A>`.
+    Fraction(Amount),
+}
+
+impl ThresholdValue {
+    fn resolve(&self, amount: Amount, max: Option<Amount>) -> Option<Amount> {
+        match self {
+            ThresholdValue::Absolute(_) => Some(amount),
+            ThresholdValue::Fraction(_) => max.map(|max| amount * max),
+        }
+    }
+
+    fn amount(&self) -> Amount {
+        match self {
+            ThresholdValue::Absolute(amount) | ThresholdValue::Fraction(amount) => *amount,
+        }
+    }
+}
+
+/// Which edge of a threshold triggers it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Triggers when the attribute drops to or below the threshold.
+    Falling,
+    /// Triggers when the attribute rises to or above the threshold.
+    Rising,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Crossing {
+    /// The attribute crossed the threshold in its `Direction`.
+    Entered,
+    /// The attribute went back past the threshold and its hysteresis.
+    Left,
+}
+
+#[derive(Clone, Copy)]
+struct MarkerCommands {
+    insert: fn(&mut EntityCommands),
+    remove: fn(&mut EntityCommands),
+}
+
+fn insert_marker<M: Component + Default>(commands: &mut EntityCommands) {
+    commands.insert(M::default());
+}
+
+fn remove_marker<M: Component>(commands: &mut EntityCommands) {
+    commands.remove::<M>();
+}
+
+/// A single watched threshold of an attribute.
+///
+/// To avoid flickering around the threshold, a triggered threshold only resets once the
+/// attribute moves `hysteresis` past it in the other direction. The hysteresis of a
+/// `ThresholdValue::Fraction` is a fraction of the max as well.
+#[derive(Clone)]
+pub struct Threshold {
+    pub name: &'static str,
+    pub value: ThresholdValue,
+    pub direction: Direction,
+    pub hysteresis: Amount,
+    marker: Option<MarkerCommands>,
+    triggered: bool,
+}
+
+impl std::fmt::Debug for Threshold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Threshold")
+            .field("name", &self.name)
+            .field("value", &self.value)
+            .field("direction", &self.direction)
+            .field("hysteresis", &self.hysteresis)
+            .field("marker", &self.marker.is_some())
+            .field("triggered", &self.triggered)
+            .finish()
+    }
+}
+
+impl Threshold {
+    pub fn new(name: &'static str, value: ThresholdValue, direction: Direction) -> Self {
+        Self {
+            name: name,
+            value: value,
+            direction: direction,
+            hysteresis: Amount::ZERO,
+            marker: None,
+            triggered: false,
+        }
+    }
+
+    pub fn falling(name: &'static str, value: ThresholdValue) -> Self {
+        Self::new(name, value, Direction::Falling)
+    }
+
+    pub fn rising(name: &'static str, value: ThresholdValue) -> Self {
+        Self::new(name, value, Direction::Rising)
+    }
+
+    pub fn with_hysteresis(mut self, hysteresis: Amount) -> Self {
+        self.hysteresis = hysteresis;
+        self
+    }
+
+    /// Insert `M` on the entity while the threshold is triggered.
+    pub fn with_marker<M>(mut self) -> Self
+    where
+        M: Component + Default,
+    {
+        self.marker = Some(MarkerCommands {
+            insert: insert_marker::<M>,
+            remove: remove_marker::<M>,
+        });
+        self
+    }
+
+    pub fn triggered(&self) -> bool {
+        self.triggered
+    }
+
+    fn update(&mut self, amount: Amount, max: Option<Amount>) -> Option<Crossing> {
+        let threshold = self.value.resolve(self.value.amount(), max)?;
+        let hysteresis = self.value.resolve(self.hysteresis, max)?;
+
+        let (enter, leave) = match self.direction {
+            Direction::Falling => (amount <= threshold, amount > threshold + hysteresis),
+            Direction::Rising => (amount >= threshold, amount < threshold - hysteresis),
+        };
+
+        if !self.triggered && enter {
+            self.triggered = true;
+            Some(Crossing::Entered)
+        } else if self.triggered && leave {
+            self.triggered = false;
+            Some(Crossing::Left)
+        } else {
+            None
+        }
+    }
+}
+
+/// Thresholds watched on the `Attribute<A>` of this entity.
+#[derive(Component, Debug, Clone)]
+pub struct Thresholds<A> {
+    thresholds: Vec<Threshold>,
+    phantom: PhantomData<A>,
+}
+
+impl<A> Default for Thresholds<A> {
+    fn default() -> Self {
+        Self {
+            thresholds: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A> Thresholds<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, threshold: Threshold) -> Self {
+        self.thresholds.push(threshold);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Threshold> {
+        self.thresholds
+            .iter()
+            .find(|threshold| threshold.name == name)
+    }
+}
+
+/// Sent when an attribute crosses one of its `Thresholds<A>`.
+#[derive(Debug)]
+pub struct ThresholdCrossed<A> {
+    pub entity: Entity,
+    pub threshold: &'static str,
+    pub crossing: Crossing,
+    phantom: PhantomData<A>,
+}
+
+impl<A> Clone for ThresholdCrossed<A> {
+    fn clone(&self) -> Self {
+        Self {
+            entity: self.entity,
+            threshold: self.threshold,
+            crossing: self.crossing,
+            phantom: PhantomData,
+        }
+    }
+}
+
+pub fn check_thresholds<A>(
+    mut commands: Commands,
+    mut events: EventWriter<ThresholdCrossed<A>>,
+    mut attributes: Query<
+        (
+            Entity,
+            &Attribute<A>,
+            Option<&Attribute<Max<A>>>,
+            &mut Thresholds<A>,
+        ),
+        Or<(
+            Changed<Attribute<A>>,
+            Changed<Attribute<Max<A>>>,
+            Added<Thresholds<A>>,
+        )>,
+    >,
+) where
+    A: 'static + Send + Sync,
+{
+    for (entity, attribute, max, mut thresholds) in attributes.iter_mut() {
+        let amount = *attribute.amount();
+        let max = max.map(|max| *max.amount());
+
+        for threshold in thresholds.thresholds.iter_mut() {
+            let crossing = match threshold.update(amount, max) {
+                Some(crossing) => crossing,
+                None => continue,
+            };
+
+            if let Some(marker) = threshold.marker {
+                let mut entity_commands = commands.entity(entity);
+                match crossing {
+                    Crossing::Entered => (marker.insert)(&mut entity_commands),
+                    Crossing::Left => (marker.remove)(&mut entity_commands),
+                }
+            }
+
+            events.send(ThresholdCrossed {
+                entity: entity,
+                threshold: threshold.name,
+                crossing: crossing,
+                phantom: PhantomData,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::plugin::AttributePlugin;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct Health;
+
+    #[derive(Component, Debug, Default, PartialEq)]
+    pub struct Enraged;
+
+    #[test]
+    fn low_health_threshold() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<Health>::default());
+
+        let boss = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(100)))
+            .insert(Attribute::<Max<Health>>::new(Amount::from_num(100)))
+            .insert(
+                Thresholds::<Health>::new()
+                    .with(
+                        Threshold::falling(
+                            "enrage",
+                            ThresholdValue::Fraction(Amount::from_num(0.25)),
+                        )
+                        .with_hysteresis(Amount::from_num(0.05))
+                        .with_marker::<Enraged>(),
+                    )
+                    .with(Threshold::falling(
+                        "death",
+                        ThresholdValue::Absolute(Amount::ZERO),
+                    )),
+            )
+            .id();
+
+        let set_health = |app: &mut App, health: i32| {
+            app.world
+                .get_mut::<Attribute<Health>>(boss)
+                .unwrap()
+                .set_amount(Amount::from_num(health));
+            app.update();
+        };
+
+        set_health(&mut app, 20);
+        assert_eq!(app.world.get::<Enraged>(boss), Some(&Enraged));
+
+        // Still within the hysteresis.
+        set_health(&mut app, 28);
+        assert_eq!(app.world.get::<Enraged>(boss), Some(&Enraged));
+
+        set_health(&mut app, 31);
+        assert_eq!(app.world.get::<Enraged>(boss), None);
+
+        set_health(&mut app, 0);
+        assert_eq!(app.world.get::<Enraged>(boss), Some(&Enraged));
+        assert!(app
+            .world
+            .get::<Thresholds<Health>>(boss)
+            .unwrap()
+            .get("death")
+            .unwrap()
+            .triggered());
+    }
+}
@@ -0,0 +1,11 @@
+use bevy::prelude::*;
+
+/// Entity that cast this ability, or applied this effect.
+#[derive(Component, PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Caster(pub Entity);
+
+impl Caster {
+    pub fn entity(&self) -> Entity {
+        self.0
+    }
+}
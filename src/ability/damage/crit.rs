@@ -0,0 +1,308 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use std::marker::PhantomData;
+
+use super::{DamageLabel, DamageQueue, DamageTag};
+use crate::ability::attribute::attribute::{Amount, Attribute};
+use crate::ability::attribute::plugin::AttributeLabel;
+
+/// Small deterministic random number generator (SplitMix64).
+///
+/// The crit rolls of the damage pipeline use the `SeededRng` component of the attacker if it has
+/// one, the `SeededRng` resource otherwise. Inserting the resource with a known seed makes
+/// replays and tests roll the same crits.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl Default for SeededRng {
+    fn default() -> Self {
+        Self::new(0x5eed)
+    }
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..1`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// `true` with probability `chance`.
+    pub fn chance(&mut self, chance: f64) -> bool {
+        self.next_f64() < chance
+    }
+}
+
+/// How crits are rolled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CritMode {
+    /// Every hit rolls independently.
+    Random,
+    /// Pseudo-random distribution, the chance starts low and grows with every hit that doesn't
+    /// crit, so the average stays the same but long streaks can't happen.
+    Pseudo,
+}
+
+/// Hits of an attacker against `A` since its last crit, for `CritMode::Pseudo`.
+///
+/// Inserted on the attacker by its first pseudo-random roll, so it goes away with the attacker.
+#[derive(Component, Debug)]
+pub struct CritMisses<A> {
+    pub count: u32,
+    phantom: PhantomData<A>,
+}
+
+impl<A> CritMisses<A> {
+    pub fn new(count: u32) -> Self {
+        Self {
+            count: count,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A> Clone for CritMisses<A> {
+    fn clone(&self) -> Self {
+        Self::new(self.count)
+    }
+}
+
+/// Average crit chance of the pseudo-random distribution with constant `c`.
+fn pseudo_chance(c: f64) -> f64 {
+    let mut expected = 0.0;
+    let mut crit_before = 0.0;
+    let mut n = 1.0;
+    loop {
+        let chance = (c * n).min(1.0);
+        let crit_now = chance * (1.0 - crit_before);
+        crit_before += crit_now;
+        expected += n * crit_now;
+        if chance >= 1.0 {
+            return 1.0 / expected;
+        }
+        n += 1.0;
+    }
+}
+
+/// Constant of the pseudo-random distribution, the `n`th hit since the last crit crits with
+/// `min(1, c * n)`, for an average chance of `chance`.
+///
+/// Chances below 1% are too rare to streak and are returned as is.
+pub fn pseudo_random_constant(chance: f64) -> f64 {
+    if chance < 0.01 || chance >= 1.0 {
+        return chance.max(0.0).min(1.0);
+    }
+
+    let (mut low, mut high) = (0.0, chance);
+    for _ in 0..50 {
+        let mid = (low + high) / 2.0;
+        if pseudo_chance(mid) < chance {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+fn amount_of<X>(query: &Query<&Attribute<X>>, entity: Entity) -> Option<Amount>
+where
+    X: 'static + Send + Sync,
+{
+    query.get(entity).ok().map(|attribute| *attribute.amount())
+}
+
+/// Rolls crits for hits whose source has an `Attribute<Chance>`, critical hits are multiplied
+/// by `Attribute<Damage>` of the source, or doubled without one, and tagged
+/// `DamageTag::Critical`. Reflected hits never crit.
+pub(super) fn register_crits<A, Chance, Damage>(app: &mut App, mode: CritMode)
+where
+    A: 'static + Send + Sync,
+    Chance: 'static + Send + Sync,
+    Damage: 'static + Send + Sync,
+{
+    app.init_resource::<SeededRng>().add_system(
+        (move |mut commands: Commands,
+               mut constants: Local<HashMap<Amount, f64>>,
+               mut queue: ResMut<DamageQueue<A>>,
+               mut world_rng: ResMut<SeededRng>,
+               mut rngs: Query<&mut SeededRng>,
+               mut misses: Query<&mut CritMisses<A>>,
+               chances: Query<&Attribute<Chance>>,
+               damages: Query<&Attribute<Damage>>| {
+            // Misses of attackers rolling for the first time, inserted after all hits.
+            let mut first_misses = HashMap::<Entity, u32>::default();
+            for hit in queue.hits.iter_mut() {
+                let source = match hit.event.source {
+                    Some(source) if !hit.event.has_tag(DamageTag::Reflected) => source,
+                    _ => continue,
+                };
+                let chance = match amount_of(&chances, source) {
+                    Some(chance) => chance,
+                    None => continue,
+                };
+
+                let missed = match misses.get(source) {
+                    Ok(misses) => misses.count,
+                    Err(_) => first_misses.get(&source).copied().unwrap_or(0),
+                };
+                let chance = match mode {
+                    CritMode::Random => chance.to_f64(),
+                    CritMode::Pseudo => {
+                        let constant = *constants
+                            .entry(chance)
+                            .or_insert_with(|| pseudo_random_constant(chance.to_f64()));
+                        constant * (missed + 1) as f64
+                    }
+                };
+
+                let crit = match rngs.get_mut(source) {
+                    Ok(mut rng) => rng.chance(chance),
+                    Err(_) => world_rng.chance(chance),
+                };
+                if mode == CritMode::Pseudo {
+                    let count = if crit { 0 } else { missed + 1 };
+                    match misses.get_mut(source) {
+                        Ok(mut misses) => misses.count = count,
+                        Err(_) => {
+                            first_misses.insert(source, count);
+                        }
+                    }
+                }
+
+                if crit {
+                    let multiplier = amount_of(&damages, source).unwrap_or(Amount::from_num(2));
+                    hit.amount = hit.amount * multiplier;
+                    hit.event.tags.push(DamageTag::Critical);
+                }
+            }
+
+            for (source, count) in first_misses {
+                commands.entity(source).insert(CritMisses::<A>::new(count));
+            }
+        })
+        .label(DamageLabel::crit::<A>())
+        .after(DamageLabel::collect::<A>())
+        .after(AttributeLabel::clamp::<Chance>())
+        .after(AttributeLabel::clamp::<Damage>())
+        .before(DamageLabel::resist::<A>())
+        .before(DamageLabel::mitigate::<A>()),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::plugin::AttributePlugin;
+    use crate::ability::damage::{DamageDealt, DamageEvent, DamagePipelinePlugin};
+    use bevy::app::Events;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct Health;
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct CritChance;
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct CritDamage;
+
+    /// Whether each of `hits` hits crit.
+    fn roll(mode: CritMode, seed: u64, hits: usize) -> Vec<bool> {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(SeededRng::new(seed))
+            .add_plugin(AttributePlugin::<Health>::default())
+            .add_plugin(
+                DamagePipelinePlugin::<Health>::default()
+                    .with_crits::<CritChance, CritDamage>(mode),
+            );
+
+        let attacker = app
+            .world
+            .spawn()
+            .insert(Attribute::<CritChance>::new(Amount::from_num(0.25)))
+            .insert(Attribute::<CritDamage>::new(Amount::from_num(1.5)))
+            .id();
+        let target = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::MAX))
+            .id();
+
+        let mut damage = app
+            .world
+            .get_resource_mut::<Events<DamageEvent<Health>>>()
+            .unwrap();
+        for _ in 0..hits {
+            damage.send(DamageEvent::new(
+                Some(attacker),
+                target,
+                Amount::from_num(10),
+            ));
+        }
+        app.update();
+        assert_eq!(
+            app.world.get::<CritMisses<Health>>(attacker).is_some(),
+            mode == CritMode::Pseudo
+        );
+
+        let events = app
+            .world
+            .get_resource::<Events<DamageDealt<Health>>>()
+            .unwrap();
+        events
+            .get_reader()
+            .iter(events)
+            .map(|dealt| {
+                let crit = dealt.tags.contains(&DamageTag::Critical);
+                assert_eq!(crit, dealt.amount == Amount::from_num(15));
+                crit
+            })
+            .collect()
+    }
+
+    fn longest_miss_streak(rolls: &[bool]) -> usize {
+        rolls
+            .split(|crit| *crit)
+            .map(|misses| misses.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn seeded_crits() {
+        let rolls = roll(CritMode::Random, 42, 2000);
+        assert_eq!(rolls, roll(CritMode::Random, 42, 2000));
+        assert_ne!(rolls, roll(CritMode::Random, 43, 2000));
+
+        let crits = rolls.iter().filter(|crit| **crit).count();
+        assert!((400..600).contains(&crits), "{} crits", crits);
+    }
+
+    #[test]
+    fn pseudo_random_crits() {
+        let constant = pseudo_random_constant(0.25);
+        assert!((constant - 0.0847).abs() < 0.0005, "{}", constant);
+
+        let rolls = roll(CritMode::Pseudo, 42, 2000);
+        assert_eq!(rolls, roll(CritMode::Pseudo, 42, 2000));
+
+        let crits = rolls.iter().filter(|crit| **crit).count();
+        assert!((400..600).contains(&crits), "{} crits", crits);
+        // The 12th hit after a crit always crits.
+        assert!(longest_miss_streak(&rolls) < 12);
+    }
+}
@@ -0,0 +1,775 @@
+//! Damage dealt to an `Attribute<A>`, usually `Attribute<Health>`.
+//!
+//! A `DamageEvent<A>` goes through a fixed set of stages, each labeled with `DamageLabel` so
+//! other systems can run in between:
+//!
+//! 1. `collect`: queues the events of this frame as `Hit<A>`s in `DamageQueue<A>`.
+//! 2. `crit`: rolls critical strikes, see `DamagePipelinePlugin::with_crits`.
+//! 3. `resist`: reduces typed damage by the resistances of the target, see
+//!    `DamagePipelinePlugin::with_resistance`.
+//! 4. `mitigate`: scales the amount by `Attribute<Amplification<A>>` of the source and
+//!    `Attribute<Reduction<A>>` of the target, see `scale_damage`.
+//! 5. `absorb`: drains the `Shields<A>` of the target, see `shield`.
+//! 6. `apply`: subtracts the amount from `Attribute<A>` without going below `Min<A>` and sends
+//!    a `DamageDealt<A>` with the result.
+//!
+//! The change is made with `ChangeCause::Damage(source)`, so `DamageHistory` records it. Lifesteal
+//! and spell vamp heal the source afterwards, see `DamagePipelinePlugin::with_vamp`, and
+//! `Attribute<Reflection<A>>` of the target deals part of the damage back to the source.
+
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use std::any::type_name;
+use std::marker::PhantomData;
+
+use crate::ability::attribute::attribute::{
+    attribute_marker, Amount, Attribute, AttributeType, Min,
+};
+use crate::ability::attribute::event::{change_attribute, AttributeChanged, ChangeCause};
+use crate::ability::attribute::plugin::AttributeLabel;
+
+pub mod crit;
+pub mod resist;
+pub mod shield;
+pub mod vamp;
+
+use crit::{register_crits, CritMode};
+use resist::{register_resistance, Mitigation, NoPenetration};
+use shield::{
+    absorb_damage, expire_shields, give_shields, GiveShield, ShieldBroken, ShieldExpired,
+    ShieldingPower,
+};
+use vamp::register_vamp;
+
+attribute_marker!(
+    /// Fraction of incoming damage to `A` ignored, `0.25` takes 25% less, negative takes more.
+    Reduction,
+    /// Bonus to the damage an entity deals to `A` of others, `0.1` deals 10% more.
+    Amplification,
+    /// Fraction of the damage to `A` dealt back to the source. Reflected hits aren't reflected
+    /// again, and don't crit, get amplified or heal through lifesteal and spell vamp.
+    Reflection,
+);
+
+/// What kind of damage a hit deals, each kind can be resisted by a different attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DamageType {
+    Physical,
+    Magic,
+    /// Ignores every resistance.
+    True,
+    /// Any other kind, e.g. `DamageType::Custom("fire")`.
+    Custom(&'static str),
+}
+
+impl Default for DamageType {
+    fn default() -> Self {
+        DamageType::True
+    }
+}
+
+/// Extra information about a hit, e.g. for on-hit effects or lifesteal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DamageTag {
+    BasicAttack,
+    Ability,
+    /// Hit more than one target at once.
+    Area,
+    /// Damage over time.
+    Periodic,
+    /// Added to hits which rolled a critical strike.
+    Critical,
+    /// Damage dealt back by `Attribute<Reflection<A>>`.
+    Reflected,
+    Custom(&'static str),
+}
+
+/// Request to damage `Attribute<A>` of `target`.
+#[derive(Debug)]
+pub struct DamageEvent<A> {
+    /// Who dealt the damage, `None` for environmental damage.
+    pub source: Option<Entity>,
+    pub target: Entity,
+    pub amount: Amount,
+    /// `DamageType::True` unless given.
+    pub kind: DamageType,
+    pub tags: Vec<DamageTag>,
+    phantom: PhantomData<A>,
+}
+
+impl<A> Clone for DamageEvent<A> {
+    fn clone(&self) -> Self {
+        Self {
+            source: self.source,
+            target: self.target,
+            amount: self.amount,
+            kind: self.kind,
+            tags: self.tags.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A> DamageEvent<A> {
+    pub fn new(source: Option<Entity>, target: Entity, amount: Amount) -> Self {
+        Self {
+            source: source,
+            target: target,
+            amount: amount,
+            kind: DamageType::default(),
+            tags: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn with_type(mut self, kind: DamageType) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn with_tag(mut self, tag: DamageTag) -> Self {
+        self.tags.push(tag);
+        self
+    }
+
+    pub fn has_tag(&self, tag: DamageTag) -> bool {
+        self.tags.contains(&tag)
+    }
+}
+
+/// A `DamageEvent<A>` going through the stages of the pipeline.
+#[derive(Debug)]
+pub struct Hit<A> {
+    pub event: DamageEvent<A>,
+    /// Damage left to deal, each stage adjusts it.
+    pub amount: Amount,
+    /// Damage absorbed by shields so far.
+    pub absorbed: Amount,
+}
+
+impl<A> Clone for Hit<A> {
+    fn clone(&self) -> Self {
+        Self {
+            event: self.event.clone(),
+            amount: self.amount,
+            absorbed: self.absorbed,
+        }
+    }
+}
+
+impl<A> From<DamageEvent<A>> for Hit<A> {
+    fn from(event: DamageEvent<A>) -> Self {
+        Self {
+            amount: event.amount,
+            absorbed: Amount::ZERO,
+            event: event,
+        }
+    }
+}
+
+/// Hits of the current frame, emptied by `apply_damage`.
+#[derive(Debug)]
+pub struct DamageQueue<A> {
+    pub hits: Vec<Hit<A>>,
+}
+
+impl<A> Default for DamageQueue<A> {
+    fn default() -> Self {
+        Self { hits: Vec::new() }
+    }
+}
+
+/// Sent once a `DamageEvent<A>` has been applied.
+#[derive(Debug)]
+pub struct DamageDealt<A> {
+    pub source: Option<Entity>,
+    pub target: Entity,
+    /// Amount of the `DamageEvent<A>`.
+    pub raw: Amount,
+    /// Amount actually removed from `Attribute<A>`, zero if the target has none.
+    pub amount: Amount,
+    /// Amount absorbed by shields.
+    pub absorbed: Amount,
+    pub kind: DamageType,
+    pub tags: Vec<DamageTag>,
+    phantom: PhantomData<A>,
+}
+
+impl<A> Clone for DamageDealt<A> {
+    fn clone(&self) -> Self {
+        Self {
+            source: self.source,
+            target: self.target,
+            raw: self.raw,
+            amount: self.amount,
+            absorbed: self.absorbed,
+            kind: self.kind,
+            tags: self.tags.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Labels for the stages of the damage pipeline of a single attribute.
+#[derive(SystemLabel, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DamageLabel {
+    /// `collect_damage::<A>`
+    Collect(&'static str),
+    /// Crits added with `DamagePipelinePlugin::with_crits`.
+    Crit(&'static str),
+    /// Resistances added with `DamagePipelinePlugin::with_resistance`.
+    Resist(&'static str),
+    /// `mitigate_damage::<A>`
+    Mitigate(&'static str),
+    /// `absorb_damage::<A>`, expired shields are removed right before.
+    Absorb(&'static str),
+    /// `apply_damage::<A>`
+    Apply(&'static str),
+}
+
+impl DamageLabel {
+    pub fn collect<A>() -> Self {
+        Self::Collect(type_name::<A>())
+    }
+
+    pub fn crit<A>() -> Self {
+        Self::Crit(type_name::<A>())
+    }
+
+    pub fn resist<A>() -> Self {
+        Self::Resist(type_name::<A>())
+    }
+
+    pub fn mitigate<A>() -> Self {
+        Self::Mitigate(type_name::<A>())
+    }
+
+    pub fn absorb<A>() -> Self {
+        Self::Absorb(type_name::<A>())
+    }
+
+    pub fn apply<A>() -> Self {
+        Self::Apply(type_name::<A>())
+    }
+}
+
+pub fn collect_damage<A>(mut events: EventReader<DamageEvent<A>>, mut queue: ResMut<DamageQueue<A>>)
+where
+    A: 'static + Send + Sync,
+{
+    queue.hits.extend(events.iter().cloned().map(Hit::from));
+}
+
+/// Damage after the `amplification` of the attacker and the `reduction` of the target.
+pub fn scale_damage(damage: Amount, amplification: Amount, reduction: Amount) -> Amount {
+    damage * (Amount::ONE + amplification) * (Amount::ONE - reduction)
+}
+
+pub fn mitigate_damage<A>(
+    mut queue: ResMut<DamageQueue<A>>,
+    amplifications: Query<&Attribute<Amplification<A>>>,
+    reductions: Query<&Attribute<Reduction<A>>>,
+) where
+    A: 'static + Send + Sync,
+{
+    for hit in queue.hits.iter_mut() {
+        let amplification = hit
+            .event
+            .source
+            .filter(|_| !hit.event.has_tag(DamageTag::Reflected))
+            .and_then(|source| amplifications.get(source).ok())
+            .map_or(Amount::ZERO, |amplification| *amplification.amount());
+        let reduction = reductions
+            .get(hit.event.target)
+            .map_or(Amount::ZERO, |reduction| *reduction.amount());
+        hit.amount = scale_damage(hit.amount, amplification, reduction).max(Amount::ZERO);
+    }
+}
+
+/// Subtracts each hit from `Attribute<A>` of its target and sends a `DamageDealt<A>`.
+///
+/// Targets without an `Attribute<A>` take no damage, but still get a `DamageDealt<A>` so what
+/// their shields absorbed isn't lost.
+pub fn apply_damage<A>(
+    mut queue: ResMut<DamageQueue<A>>,
+    mut dealt: EventWriter<DamageDealt<A>>,
+    mut events: EventWriter<AttributeChanged<A>>,
+    mut targets: Query<(&mut Attribute<A>, Option<&Attribute<Min<A>>>)>,
+) where
+    A: 'static + Send + Sync,
+{
+    for hit in queue.hits.drain(..) {
+        let target = hit.event.target;
+        let amount = match targets.get_mut(target) {
+            Ok((mut current, min)) => {
+                let old = *current.amount();
+                let mut result = old - hit.amount;
+                if let Some(min) = min {
+                    // Damage never raises an attribute that's already below its minimum.
+                    result = result.max(old.min(*min.amount()));
+                }
+                change_attribute(
+                    &mut events,
+                    target,
+                    &mut current,
+                    result,
+                    ChangeCause::Damage(hit.event.source),
+                );
+                old - result
+            }
+            Err(_) => Amount::ZERO,
+        };
+
+        dealt.send(DamageDealt {
+            source: hit.event.source,
+            target: target,
+            raw: hit.event.amount,
+            amount: amount,
+            absorbed: hit.absorbed,
+            kind: hit.event.kind,
+            tags: hit.event.tags,
+            phantom: PhantomData,
+        });
+    }
+}
+
+/// Deals `Attribute<Reflection<A>>` of the damage each target took, including what its shields
+/// absorbed, back to the source as a `DamageEvent<A>` of the same type tagged
+/// `DamageTag::Reflected`. The reflected damage goes through the pipeline the next frame.
+pub fn reflect_damage<A>(
+    mut dealt: EventReader<DamageDealt<A>>,
+    mut damage: EventWriter<DamageEvent<A>>,
+    reflections: Query<&Attribute<Reflection<A>>>,
+) where
+    A: 'static + Send + Sync,
+{
+    for dealt in dealt.iter() {
+        let source = match dealt.source {
+            Some(source) if !dealt.tags.contains(&DamageTag::Reflected) => source,
+            _ => continue,
+        };
+        let reflection = match reflections.get(dealt.target) {
+            Ok(reflection) => *reflection.amount(),
+            Err(_) => continue,
+        };
+
+        let amount = (dealt.amount + dealt.absorbed) * reflection;
+        if amount > Amount::ZERO {
+            damage.send(
+                DamageEvent::new(Some(dealt.target), source, amount)
+                    .with_type(dealt.kind)
+                    .with_tag(DamageTag::Reflected),
+            );
+        }
+    }
+}
+
+/// Attributes which already have a damage pipeline.
+///
+/// Adding `DamagePipelinePlugin<A>` again only adds its crits, resistances and vamp, and panics
+/// if one of them was already added, so no stage runs twice.
+#[derive(Debug, Default)]
+pub struct RegisteredDamagePipelines {
+    pipelines: HashSet<&'static str>,
+    configured: HashSet<(&'static str, String)>,
+}
+
+impl RegisteredDamagePipelines {
+    pub fn contains<A>(&self) -> bool {
+        self.pipelines.contains(type_name::<A>())
+    }
+
+    fn register<A>(&mut self) -> bool {
+        self.pipelines.insert(type_name::<A>())
+    }
+
+    /// Marks the `setting` of `A` as configured, panics if another plugin already did.
+    fn configure<A>(&mut self, setting: String) {
+        if !self.configured.insert((type_name::<A>(), setting.clone())) {
+            panic!(
+                "DamagePipelinePlugin<{}> was added twice with {}",
+                type_name::<A>(),
+                setting
+            );
+        }
+    }
+}
+
+/// Damage pipeline of `A`, `A` needs its own `AttributePlugin`.
+///
+/// Damage is applied after the modifiers of `A` and before its regen, clamps and thresholds.
+/// The plugin can be added more than once, e.g. by `DamagePlugin` and the game, see
+/// `RegisteredDamagePipelines`.
+/// Resistances are added per `DamageType`, e.g. armor against physical damage:
+///
+/// ```ignore
+/// app.add_plugin(
+///     DamagePipelinePlugin::<Health>::default()
+///         .with_penetrable_resistance::<Armor, PhysicalPenetration, PercentPhysicalPenetration>(
+///             DamageType::Physical,
+///             Mitigation::Multiplier,
+///         ),
+/// );
+/// ```
+pub struct DamagePipelinePlugin<A> {
+    crits: Option<Crits>,
+    resistances: Vec<Resistance>,
+    vamp: Option<Vamp>,
+    phantom: PhantomData<A>,
+}
+
+struct Crits {
+    mode: CritMode,
+    register: fn(&mut App, CritMode),
+}
+
+struct Vamp {
+    area_falloff: Amount,
+    register: fn(&mut App, Amount),
+}
+
+struct Resistance {
+    name: &'static str,
+    kind: DamageType,
+    mitigation: Mitigation,
+    register: fn(&mut App, DamageType, Mitigation),
+}
+
+impl<A> Default for DamagePipelinePlugin<A> {
+    fn default() -> Self {
+        Self {
+            crits: None,
+            resistances: Vec::new(),
+            vamp: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A> DamagePipelinePlugin<A>
+where
+    A: 'static + Send + Sync,
+{
+    /// Rolls crits from `Attribute<Chance>` of the source, multiplying the damage of critical
+    /// hits by its `Attribute<Damage>`.
+    pub fn with_crits<Chance, Damage>(mut self, mode: CritMode) -> Self
+    where
+        Chance: 'static + Send + Sync,
+        Damage: 'static + Send + Sync,
+    {
+        self.crits = Some(Crits {
+            mode: mode,
+            register: register_crits::<A, Chance, Damage>,
+        });
+        self
+    }
+
+    /// Heals the source by `Attribute<Lifesteal>` of the damage its basic attacks deal and
+    /// `Attribute<SpellVamp>` of the damage its abilities deal, area abilities scaled by
+    /// `area_falloff`. Needs the `HealPipelinePlugin<A>`.
+    pub fn with_vamp<Lifesteal, SpellVamp>(mut self, area_falloff: Amount) -> Self
+    where
+        Lifesteal: 'static + Send + Sync,
+        SpellVamp: 'static + Send + Sync,
+    {
+        self.vamp = Some(Vamp {
+            area_falloff: area_falloff,
+            register: register_vamp::<A, Lifesteal, SpellVamp>,
+        });
+        self
+    }
+
+    /// Mitigates damage of type `kind` by `Attribute<R>` of the target.
+    pub fn with_resistance<R>(self, kind: DamageType, mitigation: Mitigation) -> Self
+    where
+        R: 'static + Send + Sync,
+    {
+        self.with_penetrable_resistance::<R, NoPenetration, NoPenetration>(kind, mitigation)
+    }
+
+    /// Mitigates damage of type `kind` by `Attribute<R>` of the target, after the flat
+    /// `Attribute<Flat>` and fractional `Attribute<Percent>` penetration of the source.
+    pub fn with_penetrable_resistance<R, Flat, Percent>(
+        mut self,
+        kind: DamageType,
+        mitigation: Mitigation,
+    ) -> Self
+    where
+        R: 'static + Send + Sync,
+        Flat: 'static + Send + Sync,
+        Percent: 'static + Send + Sync,
+    {
+        self.resistances.push(Resistance {
+            name: type_name::<R>(),
+            kind: kind,
+            mitigation: mitigation,
+            register: register_resistance::<A, R, Flat, Percent>,
+        });
+        self
+    }
+}
+
+impl<A> Plugin for DamagePipelinePlugin<A>
+where
+    A: AttributeType,
+{
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RegisteredDamagePipelines>();
+        let newly_registered = {
+            let mut registered = app
+                .world
+                .get_resource_mut::<RegisteredDamagePipelines>()
+                .expect("RegisteredDamagePipelines was just initialized");
+            if self.crits.is_some() {
+                registered.configure::<A>("crits".to_string());
+            }
+            for resistance in &self.resistances {
+                registered.configure::<A>(format!(
+                    "{} against {:?} damage",
+                    resistance.name, resistance.kind
+                ));
+            }
+            if self.vamp.is_some() {
+                registered.configure::<A>("vamp".to_string());
+            }
+            registered.register::<A>()
+        };
+
+        if newly_registered {
+            add_pipeline_systems::<A>(app);
+        }
+        if let Some(crits) = &self.crits {
+            (crits.register)(app, crits.mode);
+        }
+        for resistance in &self.resistances {
+            (resistance.register)(app, resistance.kind, resistance.mitigation);
+        }
+        if let Some(vamp) = &self.vamp {
+            (vamp.register)(app, vamp.area_falloff);
+        }
+    }
+}
+
+/// Adds the stages every damage pipeline of `A` has.
+fn add_pipeline_systems<A>(app: &mut App)
+where
+    A: AttributeType,
+{
+    app.init_resource::<DamageQueue<A>>()
+        .add_event::<DamageEvent<A>>()
+        .add_event::<DamageDealt<A>>()
+        .add_event::<GiveShield<A>>()
+        .add_event::<ShieldBroken<A>>()
+        .add_event::<ShieldExpired<A>>()
+        .register_type::<Attribute<Reduction<A>>>()
+        .register_type::<Attribute<Amplification<A>>>()
+        .register_type::<Attribute<Reflection<A>>>()
+        .register_type::<Attribute<ShieldingPower<A>>>()
+        .add_system(collect_damage::<A>.label(DamageLabel::collect::<A>()))
+        .add_system(
+            mitigate_damage::<A>
+                .label(DamageLabel::mitigate::<A>())
+                .after(DamageLabel::collect::<A>())
+                .after(DamageLabel::resist::<A>()),
+        )
+        .add_system(give_shields::<A>.before(DamageLabel::absorb::<A>()))
+        .add_system(expire_shields::<A>.before(DamageLabel::absorb::<A>()))
+        .add_system(
+            absorb_damage::<A>
+                .label(DamageLabel::absorb::<A>())
+                .after(DamageLabel::mitigate::<A>()),
+        )
+        .add_system(
+            apply_damage::<A>
+                .label(DamageLabel::apply::<A>())
+                .after(DamageLabel::absorb::<A>())
+                .after(AttributeLabel::modifiers::<A>())
+                .before(AttributeLabel::regen::<A>()),
+        )
+        .add_system(reflect_damage::<A>.after(DamageLabel::apply::<A>()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::health::{DamageHistory, Health, HealthBundle, HealthPlugin};
+    use bevy::app::Events;
+    use serde::{Deserialize, Serialize};
+    use shield::{Shield, Shields};
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct CritChance;
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct CritDamage;
+
+    #[test]
+    fn damage_pipeline() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(HealthPlugin)
+            .add_plugin(DamagePipelinePlugin::<Health>::default());
+
+        let attacker = app.world.spawn().id();
+        let player = app
+            .world
+            .spawn()
+            .insert_bundle(HealthBundle::new(Amount::from_num(100), Amount::ZERO))
+            .insert(Attribute::<Min<Health>>::new(Amount::ZERO))
+            .insert(Attribute::<Reduction<Health>>::new(Amount::from_num(0.5)))
+            .id();
+        app.update();
+
+        let mut damage = app
+            .world
+            .get_resource_mut::<Events<DamageEvent<Health>>>()
+            .unwrap();
+        damage.send(DamageEvent::new(
+            Some(attacker),
+            player,
+            Amount::from_num(40),
+        ));
+        damage.send(
+            DamageEvent::new(Some(attacker), player, Amount::from_num(1000))
+                .with_tag(DamageTag::Ability),
+        );
+        app.update();
+
+        let health = app.world.get::<Attribute<Health>>(player).unwrap();
+        assert_eq!(*health.amount(), Amount::ZERO);
+
+        let events = app
+            .world
+            .get_resource::<Events<DamageDealt<Health>>>()
+            .unwrap();
+        let dealt = events
+            .get_reader()
+            .iter(events)
+            .map(|dealt| (dealt.raw, dealt.amount))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dealt,
+            vec![
+                (Amount::from_num(40), Amount::from_num(20)),
+                (Amount::from_num(1000), Amount::from_num(80)),
+            ]
+        );
+
+        let history = app.world.get::<DamageHistory>(player).unwrap();
+        assert_eq!(history.total_from(attacker), Amount::from_num(100));
+    }
+
+    #[test]
+    fn amplification() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(HealthPlugin)
+            .add_plugin(DamagePipelinePlugin::<Health>::default());
+
+        let attacker = app
+            .world
+            .spawn()
+            .insert(Attribute::<Amplification<Health>>::new(Amount::from_num(
+                0.5,
+            )))
+            .id();
+        let target = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(100)))
+            .insert(Attribute::<Reduction<Health>>::new(Amount::from_num(0.2)))
+            .id();
+
+        let mut damage = app
+            .world
+            .get_resource_mut::<Events<DamageEvent<Health>>>()
+            .unwrap();
+        damage.send(DamageEvent::new(
+            Some(attacker),
+            target,
+            Amount::from_num(20),
+        ));
+        damage.send(DamageEvent::new(None, target, Amount::from_num(20)));
+        // 20 * 1.5 * 0.8 = 24, environmental damage is only reduced.
+        app.update();
+
+        let health = app.world.get::<Attribute<Health>>(target).unwrap();
+        assert_eq!(*health.amount(), Amount::from_num(60));
+    }
+
+    #[test]
+    fn reflection() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(HealthPlugin)
+            .add_plugin(
+                DamagePipelinePlugin::<Health>::default()
+                    .with_crits::<CritChance, CritDamage>(CritMode::Random),
+            );
+
+        let attacker = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(100)))
+            .insert(Attribute::<Reflection<Health>>::new(Amount::ONE))
+            .id();
+        let target = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(100)))
+            .insert(Attribute::<Reflection<Health>>::new(Amount::from_num(0.5)))
+            .insert(Attribute::<Amplification<Health>>::new(Amount::ONE))
+            .insert(Attribute::<CritChance>::new(Amount::ONE))
+            .id();
+
+        let mut damage = app
+            .world
+            .get_resource_mut::<Events<DamageEvent<Health>>>()
+            .unwrap();
+        damage.send(
+            DamageEvent::new(Some(attacker), target, Amount::from_num(20))
+                .with_type(DamageType::Physical),
+        );
+        // Half of it comes back the next frame, without crits or amplification of the target,
+        // and isn't reflected again.
+        app.update();
+        app.update();
+        app.update();
+
+        let health = |entity| *app.world.get::<Attribute<Health>>(entity).unwrap().amount();
+        assert_eq!(health(target), Amount::from_num(80));
+        assert_eq!(health(attacker), Amount::from_num(90));
+    }
+
+    #[test]
+    fn target_without_attribute() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(HealthPlugin)
+            .add_plugin(DamagePipelinePlugin::<Health>::default());
+
+        let barrier = app
+            .world
+            .spawn()
+            .insert(Shields::<Health>::new().with(Shield::new(None, Amount::from_num(50))))
+            .id();
+
+        let mut damage = app
+            .world
+            .get_resource_mut::<Events<DamageEvent<Health>>>()
+            .unwrap();
+        damage.send(DamageEvent::new(None, barrier, Amount::from_num(30)));
+        app.update();
+
+        let events = app
+            .world
+            .get_resource::<Events<DamageDealt<Health>>>()
+            .unwrap();
+        let dealt = events
+            .get_reader()
+            .iter(events)
+            .map(|dealt| (dealt.target, dealt.amount, dealt.absorbed))
+            .collect::<Vec<_>>();
+        assert_eq!(dealt, vec![(barrier, Amount::ZERO, Amount::from_num(30))]);
+    }
+}
@@ -0,0 +1,205 @@
+use bevy::prelude::*;
+
+use std::fmt;
+
+use super::{DamageLabel, DamageQueue, DamageType};
+use crate::ability::attribute::attribute::{Amount, Attribute};
+use crate::ability::attribute::plugin::AttributeLabel;
+
+/// Stand-in for the penetration of a resistance nothing penetrates.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoPenetration;
+
+/// How a resistance reduces the damage of its type.
+#[derive(Clone, Copy)]
+pub enum Mitigation {
+    /// Scales by `damage_multiplier`, like armor and magic resist.
+    Multiplier,
+    /// The resistance is the fraction of damage ignored, `0.25` takes 25% less.
+    Fraction,
+    /// Any other function of the damage and the resistance left after penetration.
+    Formula(fn(Amount, Amount) -> Amount),
+}
+
+impl Mitigation {
+    pub fn apply(&self, damage: Amount, resist: Amount) -> Amount {
+        match self {
+            Mitigation::Multiplier => damage * damage_multiplier(resist),
+            Mitigation::Fraction => damage * (Amount::ONE - resist),
+            Mitigation::Formula(formula) => formula(damage, resist),
+        }
+    }
+}
+
+impl fmt::Debug for Mitigation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mitigation::Multiplier => write!(f, "Multiplier"),
+            Mitigation::Fraction => write!(f, "Fraction"),
+            Mitigation::Formula(_) => write!(f, "Formula"),
+        }
+    }
+}
+
+/// Multiplier of incoming damage for the given armor or magic resist.
+///
+/// `100 / (100 + resist)` for positive resists, so every point is worth the same amount of
+/// effective health, and `2 - 100 / (100 - resist)` for negative resists.
+pub fn damage_multiplier(resist: Amount) -> Amount {
+    let hundred = Amount::from_num(100);
+    if resist >= Amount::ZERO {
+        hundred / (hundred + resist)
+    } else {
+        Amount::from_num(2) - hundred / (hundred - resist)
+    }
+}
+
+/// Resist left after penetration, percentage penetration applies first.
+///
+/// Penetration can't take a resist below zero, already negative resists are left alone.
+pub fn effective_resist(resist: Amount, flat: Amount, percent: Amount) -> Amount {
+    if resist <= Amount::ZERO {
+        return resist;
+    }
+
+    (resist * (Amount::ONE - percent) - flat).max(Amount::ZERO)
+}
+
+fn amount_of<X>(query: &Query<&Attribute<X>>, entity: Option<Entity>) -> Amount
+where
+    X: 'static + Send + Sync,
+{
+    entity
+        .and_then(|entity| query.get(entity).ok())
+        .map(|attribute| *attribute.amount())
+        .unwrap_or(Amount::ZERO)
+}
+
+/// Mitigates hits of type `kind` by `Attribute<R>` of the target, penetrated by
+/// `Attribute<Flat>` and `Attribute<Percent>` of the source.
+pub(super) fn register_resistance<A, R, Flat, Percent>(
+    app: &mut App,
+    kind: DamageType,
+    mitigation: Mitigation,
+) where
+    A: 'static + Send + Sync,
+    R: 'static + Send + Sync,
+    Flat: 'static + Send + Sync,
+    Percent: 'static + Send + Sync,
+{
+    app.add_system(
+        (move |mut queue: ResMut<DamageQueue<A>>,
+               resists: Query<&Attribute<R>>,
+               flat: Query<&Attribute<Flat>>,
+               percent: Query<&Attribute<Percent>>| {
+            for hit in queue.hits.iter_mut() {
+                if hit.event.kind != kind {
+                    continue;
+                }
+
+                let resist = match resists.get(hit.event.target) {
+                    Ok(resist) => *resist.amount(),
+                    Err(_) => continue,
+                };
+                let source = hit.event.source;
+                let resist = effective_resist(
+                    resist,
+                    amount_of(&flat, source),
+                    amount_of(&percent, source),
+                );
+                hit.amount = mitigation.apply(hit.amount, resist);
+            }
+        })
+        .label(DamageLabel::resist::<A>())
+        .after(DamageLabel::collect::<A>())
+        .after(AttributeLabel::clamp::<R>())
+        .before(DamageLabel::mitigate::<A>()),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::plugin::AttributePlugin;
+    use crate::ability::damage::{DamageDealt, DamageEvent, DamagePipelinePlugin};
+    use bevy::app::Events;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct Health;
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct Armor;
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct ArmorPen;
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct PercentArmorPen;
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct FireResist;
+
+    const FIRE: DamageType = DamageType::Custom("fire");
+
+    #[test]
+    fn damage_types() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<Health>::default())
+            .add_plugin(
+                DamagePipelinePlugin::<Health>::default()
+                    .with_penetrable_resistance::<Armor, ArmorPen, PercentArmorPen>(
+                        DamageType::Physical,
+                        Mitigation::Multiplier,
+                    )
+                    .with_resistance::<FireResist>(FIRE, Mitigation::Fraction),
+            );
+
+        let attacker = app
+            .world
+            .spawn()
+            .insert(Attribute::<ArmorPen>::new(Amount::from_num(10)))
+            .insert(Attribute::<PercentArmorPen>::new(Amount::from_num(0.3)))
+            .id();
+        let target = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(1000)))
+            .insert(Attribute::<Armor>::new(Amount::from_num(100)))
+            .insert(Attribute::<FireResist>::new(Amount::from_num(0.25)))
+            .id();
+
+        let mut damage = app
+            .world
+            .get_resource_mut::<Events<DamageEvent<Health>>>()
+            .unwrap();
+        for kind in [
+            DamageType::Physical,
+            DamageType::Magic,
+            DamageType::True,
+            FIRE,
+        ] {
+            damage.send(
+                DamageEvent::new(Some(attacker), target, Amount::from_num(100)).with_type(kind),
+            );
+        }
+        app.update();
+
+        let events = app
+            .world
+            .get_resource::<Events<DamageDealt<Health>>>()
+            .unwrap();
+        let dealt = events
+            .get_reader()
+            .iter(events)
+            .map(|dealt| (dealt.kind, dealt.amount))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dealt,
+            vec![
+                // 100 armor penetrated down to 60.
+                (DamageType::Physical, Amount::from_num(62.5)),
+                (DamageType::Magic, Amount::from_num(100)),
+                (DamageType::True, Amount::from_num(100)),
+                (FIRE, Amount::from_num(75)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,521 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use super::{DamageQueue, DamageType};
+use crate::ability::attribute::attribute::{attribute_marker, Amount, Attribute};
+use crate::ability::attribute::regen::RegenClock;
+
+attribute_marker!(
+    /// Bonus to the shields an entity gives with `GiveShield<A>`, `0.2` shields 20% more.
+    ShieldingPower,
+);
+
+/// A single layer of damage absorbed before `Attribute<A>` is touched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shield {
+    /// Who gave the shield.
+    pub source: Option<Entity>,
+    /// Damage left to absorb.
+    pub amount: Amount,
+    /// Only absorbs damage of this type, everything when `None`.
+    pub kind: Option<DamageType>,
+    /// Higher priorities are drained first.
+    pub priority: i32,
+    /// Time left until the shield expires, `None` lasts until broken.
+    pub remaining: Option<Duration>,
+}
+
+impl Shield {
+    pub fn new(source: Option<Entity>, amount: Amount) -> Self {
+        Self {
+            source: source,
+            amount: amount,
+            kind: None,
+            priority: 0,
+            remaining: None,
+        }
+    }
+
+    /// Only absorbs damage of type `kind`, e.g. a magic shield.
+    pub fn only(mut self, kind: DamageType) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn lasting(mut self, duration: Duration) -> Self {
+        self.remaining = Some(duration);
+        self
+    }
+
+    pub fn absorbs(&self, kind: DamageType) -> bool {
+        self.kind.map_or(true, |only| only == kind)
+    }
+}
+
+/// Shield layers protecting `Attribute<A>` of an entity.
+///
+/// Layers are drained by priority, highest first, and in the order they were added within the
+/// same priority.
+#[derive(Component, Debug, Clone)]
+pub struct Shields<A> {
+    layers: Vec<Shield>,
+    phantom: PhantomData<A>,
+}
+
+impl<A> Default for Shields<A> {
+    fn default() -> Self {
+        Self {
+            layers: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A> Shields<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `shield` after the layers of the same or higher priority, empty shields are dropped.
+    pub fn push(&mut self, shield: Shield) {
+        if shield.amount <= Amount::ZERO {
+            return;
+        }
+
+        let index = self
+            .layers
+            .iter()
+            .position(|layer| layer.priority < shield.priority)
+            .unwrap_or(self.layers.len());
+        self.layers.insert(index, shield);
+    }
+
+    pub fn with(mut self, shield: Shield) -> Self {
+        self.push(shield);
+        self
+    }
+
+    /// Layers in the order they are drained.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Shield> {
+        self.layers.iter()
+    }
+
+    /// Total damage of type `kind` the shields can still absorb.
+    pub fn total(&self, kind: DamageType) -> Amount {
+        self.layers
+            .iter()
+            .filter(|layer| layer.absorbs(kind))
+            .map(|layer| layer.amount)
+            .sum()
+    }
+
+    /// Drains up to `amount` damage of type `kind`, returns the amount absorbed and the
+    /// layers this drained completely, which are removed.
+    pub fn absorb(&mut self, kind: DamageType, amount: Amount) -> (Amount, Vec<Shield>) {
+        let mut absorbed = Amount::ZERO;
+        let mut broken = Vec::new();
+        let mut index = 0;
+        while index < self.layers.len() && absorbed < amount {
+            let layer = &mut self.layers[index];
+            if !layer.absorbs(kind) {
+                index += 1;
+                continue;
+            }
+
+            let taken = layer.amount.min(amount - absorbed);
+            layer.amount = layer.amount - taken;
+            absorbed = absorbed + taken;
+            if layer.amount <= Amount::ZERO {
+                broken.push(self.layers.remove(index));
+            } else {
+                index += 1;
+            }
+        }
+        (absorbed, broken)
+    }
+}
+
+/// Request to add `shield` to the `Shields<A>` of `target`, which are inserted if it has none.
+///
+/// The amount is scaled by `Attribute<ShieldingPower<A>>` of the source of the shield, shields
+/// of zero or less are dropped.
+#[derive(Debug)]
+pub struct GiveShield<A> {
+    pub target: Entity,
+    pub shield: Shield,
+    phantom: PhantomData<A>,
+}
+
+impl<A> Clone for GiveShield<A> {
+    fn clone(&self) -> Self {
+        Self {
+            target: self.target,
+            shield: self.shield.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A> GiveShield<A> {
+    pub fn new(target: Entity, shield: Shield) -> Self {
+        Self {
+            target: target,
+            shield: shield,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Sent when a shield on `target` is drained completely.
+#[derive(Debug)]
+pub struct ShieldBroken<A> {
+    pub target: Entity,
+    pub shield: Shield,
+    /// Source of the damage which broke the shield.
+    pub by: Option<Entity>,
+    phantom: PhantomData<A>,
+}
+
+impl<A> Clone for ShieldBroken<A> {
+    fn clone(&self) -> Self {
+        Self {
+            target: self.target,
+            shield: self.shield.clone(),
+            by: self.by,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Sent when a shield on `target` runs out of time, with the amount it had left.
+#[derive(Debug)]
+pub struct ShieldExpired<A> {
+    pub target: Entity,
+    pub shield: Shield,
+    phantom: PhantomData<A>,
+}
+
+impl<A> Clone for ShieldExpired<A> {
+    fn clone(&self) -> Self {
+        Self {
+            target: self.target,
+            shield: self.shield.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+pub fn give_shields<A>(
+    mut commands: Commands,
+    mut gifts: EventReader<GiveShield<A>>,
+    powers: Query<&Attribute<ShieldingPower<A>>>,
+    mut shields: Query<Option<&mut Shields<A>>>,
+) where
+    A: 'static + Send + Sync,
+{
+    let mut inserted = HashMap::<Entity, Shields<A>>::default();
+    for gift in gifts.iter() {
+        let mut shield = gift.shield.clone();
+        if let Some(power) = shield.source.and_then(|source| powers.get(source).ok()) {
+            shield.amount = shield.amount * (Amount::ONE + *power.amount());
+        }
+        if shield.amount <= Amount::ZERO {
+            continue;
+        }
+
+        match shields.get_mut(gift.target) {
+            Ok(Some(mut shields)) => shields.push(shield),
+            Ok(None) => inserted.entry(gift.target).or_default().push(shield),
+            Err(_) => continue,
+        }
+    }
+
+    for (target, shields) in inserted {
+        commands.entity(target).insert(shields);
+    }
+}
+
+/// Counts down the shields on the `RegenClock`, so fixed steps expire them deterministically.
+pub fn expire_shields<A>(
+    clock: Res<RegenClock>,
+    mut expired: EventWriter<ShieldExpired<A>>,
+    mut shields: Query<(Entity, &mut Shields<A>)>,
+) where
+    A: 'static + Send + Sync,
+{
+    let delta = clock.delta();
+    for (entity, mut shields) in shields.iter_mut() {
+        if shields.layers.iter().all(|layer| layer.remaining.is_none()) {
+            continue;
+        }
+
+        for layer in shields.layers.iter_mut() {
+            if let Some(remaining) = &mut layer.remaining {
+                *remaining = remaining.saturating_sub(delta);
+            }
+        }
+
+        let (ended, layers): (Vec<_>, Vec<_>) = shields
+            .layers
+            .drain(..)
+            .partition(|layer| layer.remaining == Some(Duration::ZERO));
+        shields.layers = layers;
+        for shield in ended {
+            expired.send(ShieldExpired {
+                target: entity,
+                shield: shield,
+                phantom: PhantomData,
+            });
+        }
+    }
+}
+
+/// Lets the `Shields<A>` of each target absorb its hits.
+pub fn absorb_damage<A>(
+    mut queue: ResMut<DamageQueue<A>>,
+    mut broken: EventWriter<ShieldBroken<A>>,
+    mut shields: Query<&mut Shields<A>>,
+) where
+    A: 'static + Send + Sync,
+{
+    for hit in queue.hits.iter_mut() {
+        if hit.amount <= Amount::ZERO {
+            continue;
+        }
+
+        let target = hit.event.target;
+        if let Ok(mut shields) = shields.get_mut(target) {
+            let (absorbed, broke) = shields.absorb(hit.event.kind, hit.amount);
+            hit.amount = hit.amount - absorbed;
+            hit.absorbed = hit.absorbed + absorbed;
+            for shield in broke {
+                broken.send(ShieldBroken {
+                    target: target,
+                    shield: shield,
+                    by: hit.event.source,
+                    phantom: PhantomData,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::plugin::AttributePlugin;
+    use crate::ability::damage::{DamageDealt, DamageEvent, DamagePipelinePlugin};
+    use bevy::app::Events;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct Health;
+
+    #[test]
+    fn shields() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<Health>::default())
+            .add_plugin(DamagePipelinePlugin::<Health>::default());
+
+        let support = app.world.spawn().id();
+        let attacker = app.world.spawn().id();
+        let shields = Shields::<Health>::new()
+            .with(Shield::new(Some(support), Amount::from_num(20)))
+            .with(Shield::new(Some(support), Amount::from_num(50)).lasting(Duration::ZERO))
+            .with(
+                Shield::new(Some(support), Amount::from_num(30))
+                    .only(DamageType::Magic)
+                    .with_priority(1),
+            );
+        let target = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(100)))
+            .insert(shields)
+            .id();
+
+        let mut damage = app
+            .world
+            .get_resource_mut::<Events<DamageEvent<Health>>>()
+            .unwrap();
+        damage.send(
+            DamageEvent::new(Some(attacker), target, Amount::from_num(40))
+                .with_type(DamageType::Physical),
+        );
+        damage.send(
+            DamageEvent::new(Some(attacker), target, Amount::from_num(10))
+                .with_type(DamageType::Magic),
+        );
+        app.update();
+
+        let health = app.world.get::<Attribute<Health>>(target).unwrap();
+        assert_eq!(*health.amount(), Amount::from_num(80));
+
+        let remaining = app
+            .world
+            .get::<Shields<Health>>(target)
+            .unwrap()
+            .iter()
+            .map(|shield| shield.amount)
+            .collect::<Vec<_>>();
+        assert_eq!(remaining, vec![Amount::from_num(20)]);
+
+        let events = app
+            .world
+            .get_resource::<Events<DamageDealt<Health>>>()
+            .unwrap();
+        let dealt = events
+            .get_reader()
+            .iter(events)
+            .map(|dealt| (dealt.amount, dealt.absorbed))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dealt,
+            vec![
+                (Amount::from_num(20), Amount::from_num(20)),
+                (Amount::ZERO, Amount::from_num(10)),
+            ]
+        );
+
+        let expired = app
+            .world
+            .get_resource::<Events<ShieldExpired<Health>>>()
+            .unwrap();
+        let expired = expired.get_reader().iter(expired).count();
+        assert_eq!(expired, 1);
+
+        let broken = app
+            .world
+            .get_resource::<Events<ShieldBroken<Health>>>()
+            .unwrap();
+        let broken = broken
+            .get_reader()
+            .iter(broken)
+            .map(|broken| (broken.shield.source, broken.by))
+            .collect::<Vec<_>>();
+        assert_eq!(broken, vec![(Some(support), Some(attacker))]);
+    }
+
+    #[test]
+    fn shielding_power() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<Health>::default())
+            .add_plugin(DamagePipelinePlugin::<Health>::default());
+
+        let support = app
+            .world
+            .spawn()
+            .insert(Attribute::<ShieldingPower<Health>>::new(Amount::from_num(
+                0.5,
+            )))
+            .id();
+        let target = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(100)))
+            .id();
+
+        let mut gifts = app
+            .world
+            .get_resource_mut::<Events<GiveShield<Health>>>()
+            .unwrap();
+        gifts.send(GiveShield::new(
+            target,
+            Shield::new(Some(support), Amount::from_num(20)),
+        ));
+        gifts.send(GiveShield::new(
+            target,
+            Shield::new(None, Amount::from_num(10)),
+        ));
+        gifts.send(GiveShield::new(target, Shield::new(None, Amount::ZERO)));
+        app.update();
+
+        let remaining = app
+            .world
+            .get::<Shields<Health>>(target)
+            .unwrap()
+            .iter()
+            .map(|shield| shield.amount)
+            .collect::<Vec<_>>();
+        assert_eq!(remaining, vec![Amount::from_num(30), Amount::from_num(10)]);
+    }
+
+    #[test]
+    fn absorb_only_breaks_drained_layers() {
+        let mut shields = Shields::<Health>::new()
+            .with(Shield::new(None, Amount::ZERO).with_priority(1))
+            .with(Shield::new(None, Amount::from_num(10)))
+            .with(Shield::new(None, Amount::from_num(10)));
+        assert_eq!(shields.iter().count(), 2);
+
+        let (absorbed, broken) = shields.absorb(DamageType::True, Amount::from_num(15));
+        assert_eq!(absorbed, Amount::from_num(15));
+        assert_eq!(broken.len(), 1);
+
+        let remaining = shields
+            .iter()
+            .map(|shield| shield.amount)
+            .collect::<Vec<_>>();
+        assert_eq!(remaining, vec![Amount::from_num(5)]);
+    }
+
+    #[test]
+    fn timed_shields() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(RegenClock::fixed(Duration::from_secs(1)))
+            .add_plugin(AttributePlugin::<Health>::default())
+            .add_plugin(DamagePipelinePlugin::<Health>::default());
+
+        let target = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(100)))
+            .insert(
+                Shields::<Health>::new()
+                    .with(Shield::new(None, Amount::from_num(50)).lasting(Duration::from_secs(3))),
+            )
+            .id();
+
+        let expired = |app: &App| {
+            let events = app
+                .world
+                .get_resource::<Events<ShieldExpired<Health>>>()
+                .unwrap();
+            events.get_reader().iter(events).count()
+        };
+        for _ in 0..2 {
+            app.update();
+        }
+        assert_eq!(
+            app.world
+                .get::<Shields<Health>>(target)
+                .unwrap()
+                .iter()
+                .count(),
+            1
+        );
+        assert_eq!(expired(&app), 0);
+
+        app.update();
+        assert_eq!(
+            app.world
+                .get::<Shields<Health>>(target)
+                .unwrap()
+                .iter()
+                .count(),
+            0
+        );
+        assert_eq!(expired(&app), 1);
+    }
+}
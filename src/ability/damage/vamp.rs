@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+
+use super::{DamageDealt, DamageLabel, DamageTag};
+use crate::ability::attribute::attribute::{Amount, Attribute};
+use crate::ability::heal::{HealEvent, HealLabel};
+
+fn amount_of<X>(query: &Query<&Attribute<X>>, entity: Entity) -> Amount
+where
+    X: 'static + Send + Sync,
+{
+    query
+        .get(entity)
+        .map(|attribute| *attribute.amount())
+        .unwrap_or(Amount::ZERO)
+}
+
+/// Heals the source of every hit by `Attribute<Lifesteal>` of its basic attack damage and
+/// `Attribute<SpellVamp>` of its ability damage, area abilities scaled by `area_falloff`.
+///
+/// The damage counted is after mitigation, including what shields absorbed, reflected damage
+/// never heals. The heals are `HealEvent<A>`s, so `HealPipelinePlugin<A>` has to be added.
+pub(super) fn register_vamp<A, Lifesteal, SpellVamp>(app: &mut App, area_falloff: Amount)
+where
+    A: 'static + Send + Sync,
+    Lifesteal: 'static + Send + Sync,
+    SpellVamp: 'static + Send + Sync,
+{
+    app.add_system(
+        (move |mut dealt: EventReader<DamageDealt<A>>,
+               mut heals: EventWriter<HealEvent<A>>,
+               lifesteal: Query<&Attribute<Lifesteal>>,
+               spell_vamp: Query<&Attribute<SpellVamp>>| {
+            for dealt in dealt.iter() {
+                let source = match dealt.source {
+                    Some(source) if !dealt.tags.contains(&DamageTag::Reflected) => source,
+                    _ => continue,
+                };
+
+                let fraction = if dealt.tags.contains(&DamageTag::BasicAttack) {
+                    amount_of(&lifesteal, source)
+                } else if dealt.tags.contains(&DamageTag::Ability) {
+                    let vamp = amount_of(&spell_vamp, source);
+                    if dealt.tags.contains(&DamageTag::Area) {
+                        vamp * area_falloff
+                    } else {
+                        vamp
+                    }
+                } else {
+                    continue;
+                };
+
+                let amount = (dealt.amount + dealt.absorbed) * fraction;
+                if amount > Amount::ZERO {
+                    heals.send(HealEvent::new(Some(source), source, amount));
+                }
+            }
+        })
+        .after(DamageLabel::apply::<A>())
+        .before(HealLabel::of::<A>()),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::attribute::Max;
+    use crate::ability::attribute::plugin::AttributePlugin;
+    use crate::ability::damage::{DamageEvent, DamagePipelinePlugin};
+    use crate::ability::heal::{HealPipelinePlugin, HealingReduction};
+    use bevy::app::Events;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct Health;
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct Lifesteal;
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct SpellVamp;
+
+    #[test]
+    fn lifesteal_and_spell_vamp() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<Health>::default())
+            .add_plugin(HealPipelinePlugin::<Health>::default())
+            .add_plugin(
+                DamagePipelinePlugin::<Health>::default()
+                    .with_vamp::<Lifesteal, SpellVamp>(Amount::from_num(0.5)),
+            );
+
+        let attacker = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(50)))
+            .insert(Attribute::<Max<Health>>::new(Amount::from_num(100)))
+            .insert(Attribute::<Lifesteal>::new(Amount::from_num(0.2)))
+            .insert(Attribute::<SpellVamp>::new(Amount::from_num(0.3)))
+            .insert(Attribute::<HealingReduction<Health>>::new(
+                Amount::from_num(0.5),
+            ))
+            .id();
+        let target = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(1000)))
+            .id();
+
+        let mut damage = app
+            .world
+            .get_resource_mut::<Events<DamageEvent<Health>>>()
+            .unwrap();
+        // Heals 20 and 0.3 * 60 * 0.5 = 9, halved by the healing reduction.
+        damage.send(
+            DamageEvent::new(Some(attacker), target, Amount::from_num(100))
+                .with_tag(DamageTag::BasicAttack),
+        );
+        damage.send(
+            DamageEvent::new(Some(attacker), target, Amount::from_num(60))
+                .with_tag(DamageTag::Ability)
+                .with_tag(DamageTag::Area),
+        );
+        // Reflected and untagged hits don't heal.
+        damage.send(
+            DamageEvent::new(Some(attacker), target, Amount::from_num(100))
+                .with_tag(DamageTag::BasicAttack)
+                .with_tag(DamageTag::Reflected),
+        );
+        damage.send(DamageEvent::new(
+            Some(attacker),
+            target,
+            Amount::from_num(100),
+        ));
+        app.update();
+
+        let health = |entity| *app.world.get::<Attribute<Health>>(entity).unwrap().amount();
+        assert_eq!(health(target), Amount::from_num(640));
+        assert_eq!(health(attacker), Amount::from_num(64.5));
+    }
+}
@@ -0,0 +1,236 @@
+//! Healing of an `Attribute<A>`, usually `Attribute<Health>`.
+//!
+//! Every heal should be a `HealEvent<A>` so `HealingPower<A>` of the healer and
+//! `HealingReduction<A>` of the target apply to all of them, e.g. lifesteal from the damage
+//! pipeline. The change is made with `ChangeCause::Heal(source)`.
+
+use bevy::prelude::*;
+
+use std::any::type_name;
+use std::marker::PhantomData;
+
+use crate::ability::attribute::attribute::{
+    attribute_marker, Amount, Attribute, AttributeType, Max,
+};
+use crate::ability::attribute::event::{change_attribute, AttributeChanged, ChangeCause};
+use crate::ability::attribute::plugin::AttributeLabel;
+
+attribute_marker!(
+    /// Fraction of incoming healing of `A` ignored, `0.4` heals 40% less.
+    HealingReduction,
+    /// Bonus to the healing an entity does to `A`, `0.2` heals 20% more.
+    HealingPower,
+);
+
+/// Request to heal `Attribute<A>` of `target`.
+#[derive(Debug)]
+pub struct HealEvent<A> {
+    /// Who healed, `None` if nobody did.
+    pub source: Option<Entity>,
+    pub target: Entity,
+    pub amount: Amount,
+    phantom: PhantomData<A>,
+}
+
+impl<A> Clone for HealEvent<A> {
+    fn clone(&self) -> Self {
+        Self {
+            source: self.source,
+            target: self.target,
+            amount: self.amount,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A> HealEvent<A> {
+    pub fn new(source: Option<Entity>, target: Entity, amount: Amount) -> Self {
+        Self {
+            source: source,
+            target: target,
+            amount: amount,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Sent once a `HealEvent<A>` has been applied.
+#[derive(Debug)]
+pub struct Healed<A> {
+    pub source: Option<Entity>,
+    pub target: Entity,
+    /// Amount of the `HealEvent<A>`, before healing power and reduction.
+    pub raw: Amount,
+    /// Amount actually added to `Attribute<A>`.
+    pub amount: Amount,
+    /// Healing lost to `Max<A>`.
+    pub overheal: Amount,
+    phantom: PhantomData<A>,
+}
+
+impl<A> Clone for Healed<A> {
+    fn clone(&self) -> Self {
+        Self {
+            source: self.source,
+            target: self.target,
+            raw: self.raw,
+            amount: self.amount,
+            overheal: self.overheal,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Label of `apply_healing::<A>`.
+#[derive(SystemLabel, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HealLabel(&'static str);
+
+impl HealLabel {
+    pub fn of<A>() -> Self {
+        Self(type_name::<A>())
+    }
+}
+
+pub fn apply_healing<A>(
+    mut heals: EventReader<HealEvent<A>>,
+    mut healed: EventWriter<Healed<A>>,
+    mut events: EventWriter<AttributeChanged<A>>,
+    mut targets: Query<(
+        &mut Attribute<A>,
+        Option<&Attribute<Max<A>>>,
+        Option<&Attribute<HealingReduction<A>>>,
+    )>,
+    powers: Query<&Attribute<HealingPower<A>>>,
+) where
+    A: 'static + Send + Sync,
+{
+    for heal in heals.iter() {
+        let (mut current, max, reduction) = match targets.get_mut(heal.target) {
+            Ok(components) => components,
+            Err(_) => continue,
+        };
+
+        let mut amount = heal.amount;
+        if let Some(power) = heal.source.and_then(|source| powers.get(source).ok()) {
+            amount = amount * (Amount::ONE + *power.amount());
+        }
+        if let Some(reduction) = reduction {
+            amount = amount * (Amount::ONE - *reduction.amount());
+        }
+        let amount = amount.max(Amount::ZERO);
+
+        let old = *current.amount();
+        let mut result = old + amount;
+        if let Some(max) = max {
+            // Healing never lowers an attribute that's already above its maximum.
+            result = result.min(old.max(*max.amount()));
+        }
+        change_attribute(
+            &mut events,
+            heal.target,
+            &mut current,
+            result,
+            ChangeCause::Heal(heal.source),
+        );
+
+        healed.send(Healed {
+            source: heal.source,
+            target: heal.target,
+            raw: heal.amount,
+            amount: result - old,
+            overheal: amount - (result - old),
+            phantom: PhantomData,
+        });
+    }
+}
+
+/// Healing of `A`, `A` needs its own `AttributePlugin`.
+///
+/// Heals are applied after the modifiers of `A` and before its regen, clamps and thresholds.
+pub struct HealPipelinePlugin<A>(PhantomData<A>);
+
+impl<A> Default for HealPipelinePlugin<A> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<A> Plugin for HealPipelinePlugin<A>
+where
+    A: AttributeType,
+{
+    fn build(&self, app: &mut App) {
+        app.add_event::<HealEvent<A>>()
+            .add_event::<Healed<A>>()
+            .register_type::<Attribute<HealingReduction<A>>>()
+            .register_type::<Attribute<HealingPower<A>>>()
+            .add_system(
+                apply_healing::<A>
+                    .label(HealLabel::of::<A>())
+                    .after(AttributeLabel::modifiers::<A>())
+                    .before(AttributeLabel::regen::<A>()),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::plugin::AttributePlugin;
+    use bevy::app::Events;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct Health;
+
+    #[test]
+    fn healing() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AttributePlugin::<Health>::default())
+            .add_plugin(HealPipelinePlugin::<Health>::default());
+
+        let healer = app
+            .world
+            .spawn()
+            .insert(Attribute::<HealingPower<Health>>::new(Amount::from_num(
+                0.5,
+            )))
+            .id();
+        let player = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(50)))
+            .insert(Attribute::<Max<Health>>::new(Amount::from_num(100)))
+            .insert(Attribute::<HealingReduction<Health>>::new(
+                Amount::from_num(0.4),
+            ))
+            .id();
+
+        let mut heals = app
+            .world
+            .get_resource_mut::<Events<HealEvent<Health>>>()
+            .unwrap();
+        heals.send(HealEvent::new(None, player, Amount::from_num(50)));
+        // 40 * 1.5 * 0.6 = 36.
+        heals.send(HealEvent::new(Some(healer), player, Amount::from_num(40)));
+        app.update();
+
+        let health = app.world.get::<Attribute<Health>>(player).unwrap();
+        assert_eq!(*health.amount(), Amount::from_num(100));
+
+        let events = app.world.get_resource::<Events<Healed<Health>>>().unwrap();
+        let healed = events
+            .get_reader()
+            .iter(events)
+            .map(|healed| (healed.amount, healed.overheal))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            healed,
+            vec![
+                (Amount::from_num(30), Amount::ZERO),
+                (Amount::from_num(20), Amount::from_num(16)),
+            ]
+        );
+    }
+}
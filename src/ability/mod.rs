@@ -1,3 +1,6 @@
-pub mod projectile;
 pub mod attribute;
-pub mod react;
\ No newline at end of file
+pub mod caster;
+pub mod damage;
+pub mod heal;
+pub mod projectile;
+pub mod react;
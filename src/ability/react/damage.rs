@@ -1,12 +1,22 @@
-use bevy::app::Events;
-
-use crate::ability::attribute::attribute::Attribute;
+use crate::ability::attribute::amount::Amount;
+use crate::ability::attribute::health::Health;
+use crate::ability::caster::Caster;
+use crate::ability::damage::{
+    DamageEvent, DamageLabel, DamagePipelinePlugin, DamageTag, DamageType,
+};
 
 use super::*;
 
+/// Marks entities `EventDamage<Stat>` can damage.
 #[derive(Component, Debug, Copy, Clone)]
 pub struct Damageable<Stat>(PhantomData<Stat>);
 
+impl<Stat> Default for Damageable<Stat> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct HitDamageable<Stat> {
     intersection: IntersectionEvent,
@@ -34,58 +44,173 @@ impl<Stat> AbsorbingEntity for HitDamageable<Stat> {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub struct Health;
-
-struct DamagePlugin;
+/// Damages `Attribute<Health>` of `Damageable<Health>` entities hit by an `EventDamage<Health>`.
+///
+/// Only adds a plain `DamagePipelinePlugin<Health>`, resistances, crits or vamp come from adding
+/// a configured one as well, e.g. `standard_damage`. Health itself still needs its `HealthPlugin`.
+pub struct DamagePlugin;
 
 impl Plugin for DamagePlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<Events<HitDamageable<Health>>>()
-            .add_system(EventDamage::<Health>::check);
+        app.add_plugin(DamagePipelinePlugin::<Health>::default())
+            .add_event::<HitDamageable<Health>>()
+            .add_system(EventDamage::<Health>::check.before(DamageLabel::collect::<Health>()));
     }
 }
 
-#[derive(Component, Debug, Copy, Clone)]
-pub struct EventDamage<Attribute, Event = HitDamageable<Attribute>>(
-    PhantomData<(Attribute, Event)>,
-)
-where
-    Attribute: 'static + Send + Sync,
-    Event: 'static + Send + Sync + ReactingEntity + AbsorbingEntity;
-
-impl<Attribute, Event> Default for EventDamage<Attribute, Event>
+/// Deals `amount` damage to the `Damageable<Attribute>` entity absorbing an `Event` this entity
+/// reacts to.
+///
+/// The damage is dealt by the `Caster` of this entity, or by the entity itself without one.
+#[derive(Component, Debug, Clone)]
+pub struct EventDamage<Attribute, Event = HitDamageable<Attribute>>
 where
     Attribute: 'static + Send + Sync,
     Event: 'static + Send + Sync + ReactingEntity + AbsorbingEntity,
 {
-    fn default() -> Self {
-        Self(PhantomData)
-    }
+    pub amount: Amount,
+    pub kind: DamageType,
+    pub tags: Vec<DamageTag>,
+    phantom: PhantomData<(Attribute, Event)>,
 }
 
 impl<A, Event> EventDamage<A, Event>
 where
-    A: 'static + Send + Sync + std::fmt::Debug,
+    A: 'static + Send + Sync,
     Event: 'static + Send + Sync + ReactingEntity + AbsorbingEntity,
 {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn new(amount: Amount) -> Self {
+        Self {
+            amount: amount,
+            kind: DamageType::default(),
+            tags: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn with_type(mut self, kind: DamageType) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn with_tag(mut self, tag: DamageTag) -> Self {
+        self.tags.push(tag);
+        self
     }
 
     pub fn check(
-        commands: Commands,
         mut events: EventReader<Event>,
-        mut stat: Query<&mut Attribute<A>>,
+        mut damage: EventWriter<DamageEvent<A>>,
+        reacting: Query<(&EventDamage<A, Event>, Option<&Caster>)>,
+        damageable: Query<(), With<Damageable<A>>>,
     ) {
         for event in events.iter() {
-            if let Ok(stat) = stat.get_component_mut::<Attribute<A>>(event.absorbing_entity()) {
-                println!(
-                    "we should damage {:?}, current attr info: {:?}",
-                    event.absorbing_entity(),
-                    stat
-                );
+            let target = event.absorbing_entity();
+            if damageable.get(target).is_err() {
+                continue;
+            }
+
+            let source = event.reacting_entity();
+            if let Ok((event_damage, caster)) = reacting.get(source) {
+                let source = caster.map(Caster::entity).unwrap_or(source);
+                let mut hit = DamageEvent::new(Some(source), target, event_damage.amount);
+                hit.kind = event_damage.kind;
+                hit.tags = event_damage.tags.clone();
+                damage.send(hit);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ability::attribute::attribute::Attribute;
+    use crate::ability::attribute::health::HealthPlugin;
+    use crate::ability::damage::resist::Mitigation;
+    use crate::ability::damage::DamageDealt;
+    use bevy::app::Events;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Default, Clone, Serialize, Deserialize)]
+    pub struct Armor;
+
+    pub struct Hit {
+        projectile: Entity,
+        target: Entity,
+    }
+
+    impl ReactingEntity for Hit {
+        fn reacting_entity(&self) -> Entity {
+            self.projectile
+        }
+    }
+
+    impl AbsorbingEntity for Hit {
+        fn absorbing_entity(&self) -> Entity {
+            self.target
+        }
+    }
+
+    #[test]
+    fn event_damage() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(HealthPlugin)
+            .add_plugin(DamagePlugin)
+            .add_plugin(
+                DamagePipelinePlugin::<Health>::default()
+                    .with_resistance::<Armor>(DamageType::Physical, Mitigation::Fraction),
+            )
+            .add_event::<Hit>()
+            .add_system(EventDamage::<Health, Hit>::check.before(DamageLabel::collect::<Health>()));
+
+        let caster = app.world.spawn().id();
+        let projectile = app
+            .world
+            .spawn()
+            .insert(
+                EventDamage::<Health, Hit>::new(Amount::from_num(60))
+                    .with_type(DamageType::Physical),
+            )
+            .insert(Caster(caster))
+            .id();
+        let target = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(100)))
+            .insert(Attribute::<Armor>::new(Amount::from_num(0.5)))
+            .insert(Damageable::<Health>::default())
+            .id();
+        let wall = app
+            .world
+            .spawn()
+            .insert(Attribute::<Health>::new(Amount::from_num(100)))
+            .id();
+
+        let mut hits = app.world.get_resource_mut::<Events<Hit>>().unwrap();
+        for absorbing in [target, wall] {
+            hits.send(Hit {
+                projectile: projectile,
+                target: absorbing,
+            });
+        }
+        app.update();
+
+        // The second pipeline only adds the resistance, the hit isn't applied twice.
+        let health = |entity| *app.world.get::<Attribute<Health>>(entity).unwrap().amount();
+        assert_eq!(health(target), Amount::from_num(70));
+        assert_eq!(health(wall), Amount::from_num(100));
+
+        let events = app
+            .world
+            .get_resource::<Events<DamageDealt<Health>>>()
+            .unwrap();
+        let dealt = events
+            .get_reader()
+            .iter(events)
+            .map(|dealt| (dealt.source, dealt.target, dealt.amount))
+            .collect::<Vec<_>>();
+        assert_eq!(dealt, vec![(Some(caster), target, Amount::from_num(30))]);
+    }
+}
@@ -1,4 +1,11 @@
+// Lets the code generated by `forte_derive` refer to `::forte` inside this crate too.
+extern crate self as forte;
+
 pub mod ability;
-pub mod attribute;
 pub mod effect;
-pub mod prelude;
\ No newline at end of file
+pub mod prelude;
+
+#[doc(hidden)]
+pub use bevy;
+#[doc(hidden)]
+pub use serde;